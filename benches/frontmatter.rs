@@ -0,0 +1,52 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ovq::frontmatter;
+use std::fs;
+use std::hint::black_box;
+use std::path::PathBuf;
+
+fn write_notes(count: usize) -> (PathBuf, Vec<PathBuf>) {
+    let dir = std::env::temp_dir().join(format!("ovq-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut header = String::from("---\nstatus: active\ntags: [project, work]\n");
+    for i in 0..40 {
+        header.push_str(&format!(
+            "field{i}:\n  title: Value {i}\n  items: [a, b, c, d]\n"
+        ));
+    }
+    header.push_str("---\n# Body\n");
+
+    let paths = (0..count)
+        .map(|i| {
+            let path = dir.join(format!("note{i}.md"));
+            fs::write(&path, &header).unwrap();
+            path
+        })
+        .collect();
+    (dir, paths)
+}
+
+fn bench_frontmatter(c: &mut Criterion) {
+    let (dir, paths) = write_notes(200);
+    let keys = ["status".to_string(), "tags".to_string()];
+
+    c.bench_function("parse all keys", |b| {
+        b.iter(|| {
+            for path in &paths {
+                black_box(frontmatter::parse_frontmatter(path, None).unwrap());
+            }
+        })
+    });
+    c.bench_function("parse referenced keys", |b| {
+        b.iter(|| {
+            for path in &paths {
+                black_box(frontmatter::parse_frontmatter(path, Some(&keys)).unwrap());
+            }
+        })
+    });
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+criterion_group!(benches, bench_frontmatter);
+criterion_main!(benches);
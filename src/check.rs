@@ -0,0 +1,159 @@
+use crate::graph::{note_links, LinkGraph};
+use crate::link::{Link, LinkResolver};
+use crate::markdown::{block_ids, headings, slug};
+use crate::note::Note;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+pub struct BrokenLink<'a> {
+    pub note: &'a Note,
+    pub line: usize,
+    pub link: Link,
+}
+
+pub fn broken_links<'a>(
+    notes: &'a [Note],
+    resolver: &LinkResolver,
+    attachments: &LinkResolver,
+) -> Vec<BrokenLink<'a>> {
+    let by_path: HashMap<&Path, &Note> = notes.iter().map(|n| (n.rel_path(), n)).collect();
+    let mut broken = Vec::new();
+
+    for note in notes {
+        let mut header: Option<Vec<String>> = None;
+
+        for r in note_links(note) {
+            let target = &r.link.target;
+            let resolved = match resolver.resolve(target) {
+                Some(path) => by_path
+                    .get(path)
+                    .is_none_or(|n| subpath_exists(n, r.link.subpath.as_deref())),
+                None => attachments.resolve(target).is_some(),
+            };
+            if resolved {
+                continue;
+            }
+
+            let line = r.line.unwrap_or_else(|| {
+                let lines = header.get_or_insert_with(|| header_lines(note));
+                lines
+                    .iter()
+                    .position(|l| l.contains(target.as_str()))
+                    .map_or(1, |idx| idx + 1)
+            });
+
+            broken.push(BrokenLink {
+                note,
+                line,
+                link: r.link,
+            });
+        }
+    }
+
+    broken
+}
+
+fn subpath_exists(note: &Note, subpath: Option<&str>) -> bool {
+    let Some(subpath) = subpath.filter(|s| !s.is_empty()) else {
+        return true;
+    };
+    let Some(body) = note.body() else {
+        return false;
+    };
+
+    if let Some(id) = subpath.strip_prefix('^') {
+        return block_ids(&body.text)
+            .iter()
+            .any(|b| b.eq_ignore_ascii_case(id));
+    }
+
+    let wanted = slug(subpath.rsplit('#').next().unwrap_or(subpath));
+    headings(&body.text).iter().any(|h| slug(&h.text) == wanted)
+}
+
+fn header_lines(note: &Note) -> Vec<String> {
+    let header_len = note.body().map_or(0, |b| b.first_line);
+    File::open(&note.path)
+        .map(|file| {
+            BufReader::new(file)
+                .lines()
+                .take(header_len)
+                .map_while(Result::ok)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn orphans<'a>(notes: &'a [Note], graph: &LinkGraph) -> Vec<&'a Note> {
+    notes
+        .iter()
+        .filter(|n| {
+            graph.inlinks(n.rel_path()).is_empty() && graph.outlinks(n.rel_path()).is_empty()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Body;
+    use std::path::{Path, PathBuf};
+
+    fn note(rel_path: &str, yaml: &str, body: &str) -> Note {
+        Note::new(PathBuf::from(rel_path), serde_yaml::from_str(yaml).unwrap())
+            .with_file_info(Path::new(rel_path))
+            .with_body(Body {
+                text: body.to_string(),
+                first_line: 3,
+            })
+    }
+
+    #[test]
+    fn test_broken_links_and_orphans() {
+        let notes = vec![
+            note(
+                "A.md",
+                "up: \"[[B]]\"",
+                "[[Missing#Heading]]\n![[diagram.png]] ![[gone.pdf]]",
+            ),
+            note(
+                "B.md",
+                "status: done",
+                "# Goals: 2024\nship it ^ship\n[[A#Nope]] [[A^gone]]",
+            ),
+            note(
+                "C.md",
+                "links: [\"[[B#Goals 2024]]\", \"[[B#^ship]]\"]",
+                "[[B#Goals#Missing]] [spec](Missing%20Spec.md)",
+            ),
+            note("Lonely.md", "status: done", "nothing here"),
+        ];
+        let resolver = LinkResolver::new(notes.iter().map(|n| n.rel_path()));
+        let attachments = LinkResolver::new([Path::new("assets/diagram.png")]);
+
+        let broken: Vec<(usize, String)> = broken_links(&notes, &resolver, &attachments)
+            .into_iter()
+            .map(|b| (b.line, b.link.text))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                (4, "[[Missing#Heading]]".to_string()),
+                (5, "![[gone.pdf]]".to_string()),
+                (6, "[[A#Nope]]".to_string()),
+                (6, "[[A^gone]]".to_string()),
+                (4, "[[B#Goals#Missing]]".to_string()),
+                (4, "[spec](Missing%20Spec.md)".to_string()),
+            ]
+        );
+
+        let graph = LinkGraph::build(&notes, &resolver);
+        let lonely: Vec<&Path> = orphans(&notes, &graph)
+            .iter()
+            .map(|n| n.rel_path())
+            .collect();
+        assert_eq!(lonely, vec![Path::new("Lonely.md")]);
+    }
+}
@@ -0,0 +1,420 @@
+use crate::frontmatter::{self, FrontmatterError};
+use crate::yaml_edit;
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    Set(String, Value),
+    Unset(String),
+}
+
+#[derive(Debug)]
+pub enum EditError {
+    Frontmatter(FrontmatterError),
+    Unsupported(String),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::Frontmatter(e) => e.fmt(f),
+            EditError::Unsupported(key) => write!(
+                f,
+                "cannot edit '{}' without reformatting the frontmatter",
+                key
+            ),
+        }
+    }
+}
+
+impl From<FrontmatterError> for EditError {
+    fn from(e: FrontmatterError) -> Self {
+        EditError::Frontmatter(e)
+    }
+}
+
+impl Edit {
+    fn key(&self) -> &str {
+        match self {
+            Edit::Set(key, _) | Edit::Unset(key) => key,
+        }
+    }
+}
+
+pub fn parse_assignment(assignment: &str) -> Result<Edit, String> {
+    let Some((key, value)) = assignment.split_once('=') else {
+        return Err(format!("expected KEY=VALUE, got '{}'", assignment));
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("missing property name in '{}'", assignment));
+    }
+    let value = serde_yaml::from_str(value)
+        .ok()
+        .filter(|parsed| is_assignable(value, parsed))
+        .unwrap_or_else(|| Value::String(value.to_string()));
+    Ok(Edit::Set(key.to_string(), value))
+}
+
+// Only scalars and flow lists of scalars are taken from YAML; `[[Note]]` or `Re: budget`
+// would otherwise turn into nested lists and mappings.
+fn is_assignable(text: &str, value: &Value) -> bool {
+    let is_scalar = |v: &Value| {
+        matches!(
+            v,
+            Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_)
+        )
+    };
+    match value {
+        Value::Sequence(items) => text.trim_start().starts_with('[') && items.iter().all(is_scalar),
+        _ => is_scalar(value),
+    }
+}
+
+pub fn apply(content: &str, edits: &[Edit]) -> Result<String, EditError> {
+    let span = frontmatter::frontmatter_span(content);
+    if span.is_none() && edits.iter().all(|e| matches!(e, Edit::Unset(_))) {
+        return Ok(content.to_string());
+    }
+    let (head, yaml, tail) = match &span {
+        Some((yaml, _)) => (
+            &content[..yaml.start],
+            &content[yaml.clone()],
+            &content[yaml.end..],
+        ),
+        None => {
+            let body = content.strip_prefix('\u{feff}').unwrap_or(content);
+            (&content[..content.len() - body.len()], "", body)
+        }
+    };
+    let newline = match content.find('\n') {
+        Some(end) if content[..end].ends_with('\r') => "\r\n",
+        _ => "\n",
+    };
+
+    let Value::Mapping(mut properties) = frontmatter::parse_yaml(yaml, None, 1)? else {
+        return Err(FrontmatterError::Yaml {
+            message: "frontmatter is not a mapping".to_string(),
+            line: 2,
+            column: 1,
+        }
+        .into());
+    };
+
+    // Every textual edit is re-parsed and checked against the same edit applied to the parsed
+    // mapping, so layouts the editor does not understand are refused instead of rewritten.
+    let mut yaml = yaml.to_string();
+    for edit in edits {
+        let edited = match edit {
+            Edit::Set(key, value) => yaml_edit::set(&yaml, key, value, newline),
+            Edit::Unset(key) => yaml_edit::unset(&yaml, key),
+        };
+        apply_to_mapping(&mut properties, edit);
+        yaml = edited
+            .filter(|edited| {
+                frontmatter::parse_yaml(edited, None, 1)
+                    .is_ok_and(|v| v == Value::Mapping(properties.clone()))
+            })
+            .ok_or_else(|| EditError::Unsupported(edit.key().to_string()))?;
+    }
+
+    Ok(match span {
+        Some(_) => format!("{}{}{}", head, yaml, tail),
+        None => format!("{}---{nl}{}---{nl}{}", head, yaml, tail, nl = newline),
+    })
+}
+
+fn apply_to_mapping(properties: &mut Mapping, edit: &Edit) {
+    match edit {
+        Edit::Set(key, value) => {
+            let key = find_key(properties, key).unwrap_or_else(|| Value::from(key.as_str()));
+            properties.insert(key, value.clone());
+        }
+        Edit::Unset(key) => {
+            if let Some(key) = find_key(properties, key) {
+                properties.shift_remove(&key);
+            }
+        }
+    }
+}
+
+fn find_key(properties: &Mapping, key: &str) -> Option<Value> {
+    properties
+        .keys()
+        .find(|k| k.as_str().is_some_and(|k| k.eq_ignore_ascii_case(key)))
+        .cloned()
+}
+
+pub fn write_note(path: &Path, content: &str) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let permissions = fs::metadata(&path)?.permissions();
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, content)?;
+    fs::set_permissions(&tmp, permissions)
+        .and_then(|_| fs::rename(&tmp, &path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+pub fn unified_diff(label: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let lines = diff_lines(&old, &new);
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&i| !matches!(lines[i], Line::Same(_)))
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", label, label);
+    let mut hunk_start = 0;
+    while hunk_start < changed.len() {
+        let mut hunk_end = hunk_start;
+        while hunk_end + 1 < changed.len()
+            && changed[hunk_end + 1] - changed[hunk_end] <= 2 * CONTEXT_LINES + 1
+        {
+            hunk_end += 1;
+        }
+        let start = changed[hunk_start].saturating_sub(CONTEXT_LINES);
+        let end = (changed[hunk_end] + CONTEXT_LINES + 1).min(lines.len());
+        write_hunk(&mut out, &lines, start, end);
+        hunk_start = hunk_end + 1;
+    }
+    out
+}
+
+fn write_hunk(out: &mut String, lines: &[Line], start: usize, end: usize) {
+    let old_before = lines[..start]
+        .iter()
+        .filter(|l| !matches!(l, Line::Added(_)))
+        .count();
+    let new_before = lines[..start]
+        .iter()
+        .filter(|l| !matches!(l, Line::Removed(_)))
+        .count();
+    let hunk = &lines[start..end];
+    let old_count = hunk.iter().filter(|l| !matches!(l, Line::Added(_))).count();
+    let new_count = hunk
+        .iter()
+        .filter(|l| !matches!(l, Line::Removed(_)))
+        .count();
+
+    out.push_str(&format!(
+        "@@ -{} +{} @@\n",
+        hunk_range(old_before, old_count),
+        hunk_range(new_before, new_count)
+    ));
+    for line in hunk {
+        let (prefix, text) = match line {
+            Line::Same(text) => (' ', text),
+            Line::Removed(text) => ('-', text),
+            Line::Added(text) => ('+', text),
+        };
+        out.push(prefix);
+        out.push_str(text);
+        if !text.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+fn hunk_range(before: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", before),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{}", before + 1, count),
+    }
+}
+
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // Longest common subsequence over the changed middle; unchanged bodies are trimmed above.
+    let (n, m) = (old_mid.len(), new_mid.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|l| Line::Same(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old_mid[i] == new_mid[j] {
+            lines.push(Line::Same(old_mid[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(Line::Removed(old_mid[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new_mid[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|l| Line::Same(l)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(assignment: &str) -> Edit {
+        parse_assignment(assignment).unwrap()
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            set("done=true"),
+            Edit::Set("done".into(), Value::Bool(true))
+        );
+        assert_eq!(set("priority=2"), Edit::Set("priority".into(), 2.into()));
+        assert_eq!(
+            set("due=2024-05-01"),
+            Edit::Set("due".into(), "2024-05-01".into())
+        );
+        assert_eq!(
+            set("tags=[a, b]"),
+            Edit::Set("tags".into(), serde_yaml::from_str("[a, b]").unwrap())
+        );
+        assert_eq!(
+            set("title=a: b: c"),
+            Edit::Set("title".into(), "a: b: c".into())
+        );
+        assert_eq!(
+            set("related=[\"[[A]]\", b]"),
+            Edit::Set(
+                "related".into(),
+                serde_yaml::from_str("[\"[[A]]\", b]").unwrap()
+            )
+        );
+        assert!(parse_assignment("status").is_err());
+        assert!(parse_assignment("=done").is_err());
+    }
+
+    #[test]
+    fn test_parse_assignment_keeps_text() {
+        assert_eq!(
+            set("project=[[Graph0mane]]"),
+            Edit::Set("project".into(), "[[Graph0mane]]".into())
+        );
+        assert_eq!(
+            set("title=Re: budget"),
+            Edit::Set("title".into(), "Re: budget".into())
+        );
+        assert_eq!(
+            set("links=[[[A]], [[B]]]"),
+            Edit::Set("links".into(), "[[[A]], [[B]]]".into())
+        );
+        assert_eq!(
+            set("meta={a: 1}"),
+            Edit::Set("meta".into(), "{a: 1}".into())
+        );
+        assert_eq!(set("items=- a"), Edit::Set("items".into(), "- a".into()));
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let content = "---\nstatus: active\nDraft: true\n---\n# Body\n";
+        let edited = apply(
+            content,
+            &[
+                set("status=done"),
+                set("priority=2"),
+                Edit::Unset("draft".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(edited, "---\nstatus: done\npriority: 2\n---\n# Body\n");
+
+        assert_eq!(
+            apply("# Body\n", &[set("status=done")]).unwrap(),
+            "---\nstatus: done\n---\n# Body\n"
+        );
+        assert_eq!(
+            apply("# Body\n", &[Edit::Unset("status".into())]).unwrap(),
+            "# Body\n"
+        );
+        assert_eq!(
+            apply("---\r\na: 1\r\n---\r\n", &[set("b=2")]).unwrap(),
+            "---\r\na: 1\r\nb: 2\r\n---\r\n"
+        );
+        assert_eq!(
+            apply("\u{feff}# Body\r\n", &[set("status=done")]).unwrap(),
+            "\u{feff}---\r\nstatus: done\r\n---\r\n# Body\r\n"
+        );
+        assert!(apply("---\n- a\n---\n", &[set("b=2")]).is_err());
+    }
+
+    #[test]
+    fn test_apply_preserves_formatting() {
+        let content = "---\n# status tracking\nstatus: active # see board\ntags: [a, b]\naliases:\n  - Plan\ndraft: true\n---\n# Body\n";
+        let edited = apply(
+            content,
+            &[
+                set("status=done"),
+                set("tags=[a, b, c]"),
+                Edit::Unset("draft".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            edited,
+            "---\n# status tracking\nstatus: done # see board\ntags: [a, b, c]\naliases:\n  - Plan\n---\n# Body\n"
+        );
+
+        let anchored = "---\nbase: &b x\ncopy: *b\n---\n";
+        assert!(matches!(
+            apply(anchored, &[set("base=y")]),
+            Err(EditError::Unsupported(key)) if key == "base"
+        ));
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "---\nstatus: active\n---\n1\n2\n3\n4\n5\n6\n7\n8\n9\nend";
+        let new = "---\nstatus: done\n---\n1\n2\n3\n4\n5\n6\n7\n8\n9\nEND";
+        assert_eq!(
+            unified_diff("note.md", old, new),
+            "--- note.md\n+++ note.md\n\
+             @@ -1,5 +1,5 @@\n ---\n-status: active\n+status: done\n ---\n 1\n 2\n\
+             @@ -10,4 +10,4 @@\n 7\n 8\n 9\n-end\n\\ No newline at end of file\n+END\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            unified_diff("note.md", "# Body\n", "---\na: 1\n---\n# Body\n"),
+            "--- note.md\n+++ note.md\n@@ -1 +1,4 @@\n+---\n+a: 1\n+---\n # Body\n"
+        );
+        assert_eq!(unified_diff("note.md", old, old), "");
+    }
+}
@@ -1,23 +1,283 @@
-use serde_yaml::Value;
-use std::fs;
+use regex::Regex;
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::LazyLock;
 
-pub fn parse_frontmatter(path: &Path) -> Option<Value> {
-    let content = fs::read_to_string(path).ok()?;
-    extract_and_parse(&content)
+const MAX_HEADER_BYTES: usize = 1024 * 1024;
+
+static ERROR_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" at line \d+ column \d+").unwrap());
+
+#[derive(Debug)]
+pub enum FrontmatterError {
+    Io(io::Error),
+    Encoding {
+        line: usize,
+    },
+    TooLarge {
+        limit: usize,
+    },
+    Yaml {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    Json {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl FrontmatterError {
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            FrontmatterError::Io(_) | FrontmatterError::TooLarge { .. } => None,
+            FrontmatterError::Encoding { line }
+            | FrontmatterError::Yaml { line, .. }
+            | FrontmatterError::Json { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl fmt::Display for FrontmatterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontmatterError::Io(e) => write!(f, "cannot read file: {}", e),
+            FrontmatterError::Encoding { line } => {
+                write!(f, "invalid UTF-8 at line {}", line)
+            }
+            FrontmatterError::TooLarge { limit } => {
+                write!(f, "frontmatter is larger than {} bytes", limit)
+            }
+            FrontmatterError::Yaml {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "invalid YAML at line {}, column {}: {}",
+                line, column, message
+            ),
+            FrontmatterError::Json {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "invalid JSON at line {}, column {}: {}",
+                line, column, message
+            ),
+        }
+    }
+}
+
+pub fn parse_frontmatter(
+    path: &Path,
+    keys: Option<&[String]>,
+) -> Result<Option<Value>, FrontmatterError> {
+    let file = File::open(path).map_err(FrontmatterError::Io)?;
+    match read_header(BufReader::new(file), MAX_HEADER_BYTES)? {
+        Some(header) => extract_and_parse(&header, keys),
+        None => Ok(None),
+    }
+}
+
+fn read_header(mut reader: impl BufRead, limit: usize) -> Result<Option<String>, FrontmatterError> {
+    let mut header = Vec::new();
+
+    loop {
+        let start = header.len();
+        let remaining = (limit + 1 - start) as u64;
+        let read = reader
+            .by_ref()
+            .take(remaining)
+            .read_until(b'\n', &mut header)
+            .map_err(FrontmatterError::Io)?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        let line = &header[start..];
+        if start == 0 {
+            let line = line.strip_prefix("\u{feff}".as_bytes()).unwrap_or(line);
+            if line.trim_ascii_end() != b"---" {
+                return Ok(None);
+            }
+        } else if matches!(line.trim_ascii_end(), b"---" | b"...") {
+            return decode(header).map(Some);
+        }
+
+        if header.len() > limit {
+            return Err(FrontmatterError::TooLarge { limit });
+        }
+    }
+}
+
+pub struct Document {
+    pub frontmatter: Option<Value>,
+    pub body: String,
+    pub body_line: usize,
+}
+
+pub fn parse_document(path: &Path, keys: Option<&[String]>) -> Result<Document, FrontmatterError> {
+    split_document(read_file(path)?, keys)
+}
+
+fn split_document(content: String, keys: Option<&[String]>) -> Result<Document, FrontmatterError> {
+    let header = read_header(content.as_bytes(), MAX_HEADER_BYTES)?;
+    let Some((yaml_str, body)) = header.and(split_frontmatter(&content)) else {
+        return Ok(Document {
+            frontmatter: None,
+            body: content,
+            body_line: 0,
+        });
+    };
+
+    let frontmatter = parse_yaml(yaml_str, keys, 1)?;
+    let body_start = content.len() - body.len();
+    Ok(Document {
+        frontmatter: Some(frontmatter),
+        body_line: content[..body_start].matches('\n').count(),
+        body: body.to_string(),
+    })
+}
+
+pub fn read_yaml(path: &Path, keys: Option<&[String]>) -> Result<Value, FrontmatterError> {
+    parse_yaml(&read_file(path)?, keys, 0)
+}
+
+pub fn json_error(e: serde_json::Error) -> FrontmatterError {
+    FrontmatterError::Json {
+        message: ERROR_LOCATION.replace_all(&e.to_string(), "").into_owned(),
+        line: e.line(),
+        column: e.column(),
+    }
+}
+
+pub fn read_file(path: &Path) -> Result<String, FrontmatterError> {
+    decode(fs::read(path).map_err(FrontmatterError::Io)?)
+}
+
+fn decode(bytes: Vec<u8>) -> Result<String, FrontmatterError> {
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        FrontmatterError::Encoding {
+            line: valid.iter().filter(|&&b| b == b'\n').count() + 1,
+        }
+    })
 }
 
-fn extract_and_parse(content: &str) -> Option<Value> {
-    let trimmed = content.trim_start();
-    if !trimmed.starts_with("---") {
+fn extract_and_parse(
+    content: &str,
+    keys: Option<&[String]>,
+) -> Result<Option<Value>, FrontmatterError> {
+    match split_frontmatter(content) {
+        Some((yaml_str, _)) => parse_yaml(yaml_str, keys, 1).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub fn parse_yaml(
+    yaml_str: &str,
+    keys: Option<&[String]>,
+    first_line: usize,
+) -> Result<Value, FrontmatterError> {
+    if yaml_str.trim().is_empty() {
+        return Ok(Value::Mapping(Mapping::new()));
+    }
+    if let Some(keys) = keys {
+        if let Ok(value) = KeyFilter(keys).deserialize(serde_yaml::Deserializer::from_str(yaml_str))
+        {
+            return Ok(value);
+        }
+    }
+    serde_yaml::from_str(yaml_str).map_err(|e| {
+        let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+        let message = ERROR_LOCATION.replace_all(&e.to_string(), "").into_owned();
+        FrontmatterError::Yaml {
+            message,
+            line: line + first_line,
+            column,
+        }
+    })
+}
+
+// Decodes only the wanted top-level keys and skips the rest without building values.
+// Anything that is not a mapping falls back to a full parse in `parse_yaml`.
+struct KeyFilter<'a>(&'a [String]);
+
+impl<'de> DeserializeSeed<'de> for KeyFilter<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeyFilter<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a frontmatter mapping")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut mapping = Mapping::new();
+        while let Some(key) = map.next_key::<Value>()? {
+            let wanted = key
+                .as_str()
+                .map(str::to_lowercase)
+                .is_some_and(|k| self.0.contains(&k));
+            if wanted {
+                mapping.insert(key, map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(Value::Mapping(mapping))
+    }
+}
+
+fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let (yaml, body_start) = frontmatter_span(content)?;
+    Some((&content[yaml], &content[body_start..]))
+}
+
+pub fn frontmatter_span(content: &str) -> Option<(Range<usize>, usize)> {
+    let mut offset = if content.starts_with('\u{feff}') {
+        '\u{feff}'.len_utf8()
+    } else {
+        0
+    };
+    let mut lines = content[offset..].split_inclusive('\n').map(|line| {
+        let start = offset;
+        offset += line.len();
+        (start, line)
+    });
+
+    let (first_start, first) = lines.next()?;
+    if !is_fence(first, "---") {
         return None;
     }
+    let yaml_start = first_start + first.len();
+
+    for (start, line) in lines {
+        if is_fence(line, "---") || is_fence(line, "...") {
+            return Some((yaml_start..start, start + line.len()));
+        }
+    }
 
-    let after_first = &trimmed[3..];
-    let end_idx = after_first.find("\n---")?;
-    let yaml_str = &after_first[..end_idx];
+    None
+}
 
-    serde_yaml::from_str(yaml_str).ok()
+fn is_fence(line: &str, fence: &str) -> bool {
+    line.trim_end() == fence
 }
 
 #[cfg(test)]
@@ -31,13 +291,145 @@ title: Test
 tags: [a, b]
 ---
 Body content"#;
-        let fm = extract_and_parse(content).unwrap();
+        let fm = extract_and_parse(content, None).unwrap().unwrap();
         assert_eq!(fm["title"], "Test");
     }
 
+    #[test]
+    fn test_split_body() {
+        let content = "---\ntitle: Test\n---\nstatus:: active\nmore";
+        let (yaml_str, body) = split_frontmatter(content).unwrap();
+        assert_eq!(yaml_str, "title: Test\n");
+        assert_eq!(body, "status:: active\nmore");
+    }
+
+    #[test]
+    fn test_delimiter_corpus() {
+        let accepted = [
+            ("---\ntitle: Test\n---\nbody", "body"),
+            ("---\r\ntitle: Test\r\n---\r\nbody", "body"),
+            ("\u{feff}---\ntitle: Test\n---\nbody", "body"),
+            ("---\ntitle: Test\n...\nbody", "body"),
+            ("--- \ntitle: Test\n---\t\nbody", "body"),
+            ("---\ntitle: Test\n---", ""),
+            ("---\ntitle: Test\n----\nx: 1\n---\nbody", "body"),
+            ("---\ntitle: Test\n---text: 1\n---\nbody", "body"),
+        ];
+        for (content, expected_body) in accepted {
+            let (yaml_str, body) =
+                split_frontmatter(content).unwrap_or_else(|| panic!("rejected {:?}", content));
+            assert!(yaml_str.starts_with("title: Test"), "{:?}", content);
+            assert_eq!(body, expected_body, "{:?}", content);
+        }
+
+        let rejected = [
+            "----\ntitle: Test\n---\n",
+            "---foo\ntitle: Test\n---\n",
+            "\n---\ntitle: Test\n---\n",
+            "  ---\ntitle: Test\n---\n",
+            "---\ntitle: Test\n",
+            "---\ntitle: Test\n -- \n",
+            "Body\n---\ntitle: Test\n---\n",
+        ];
+        for content in rejected {
+            assert!(split_frontmatter(content).is_none(), "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn test_empty_and_crlf_frontmatter() {
+        let fm = extract_and_parse("---\n---\nbody", None).unwrap().unwrap();
+        assert_eq!(fm, Value::Mapping(Mapping::new()));
+
+        let fm = extract_and_parse("---\r\ntitle: Test\r\ntags: [a]\r\n---\r\n", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fm["title"], "Test");
+
+        let fm = extract_and_parse("---\ntitle: Test\n---text: 1\n---\n", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fm["---text"], 1);
+    }
+
+    #[test]
+    fn test_yaml_error_location() {
+        let err =
+            extract_and_parse("---\ntitle: Test\nstatus: [open\n---\nbody", None).unwrap_err();
+        assert!(
+            matches!(err, FrontmatterError::Yaml { line: 4, .. }),
+            "{:?}",
+            err
+        );
+        assert_eq!(err.line(), Some(4));
+        assert!(err.to_string().starts_with("invalid YAML at line 4"));
+    }
+
+    #[test]
+    fn test_read_header_stops_at_fence() {
+        let content = format!("---\ntitle: Test\n---\n{}", "x".repeat(10_000));
+        let mut reader = io::Cursor::new(content.as_bytes());
+        let header = read_header(&mut reader, 1024).unwrap().unwrap();
+        assert_eq!(header, "---\ntitle: Test\n---\n");
+        assert_eq!(reader.position(), header.len() as u64);
+
+        let mut reader = io::Cursor::new("# Heading\n---\nx: 1\n---\n".as_bytes());
+        assert!(read_header(&mut reader, 1024).unwrap().is_none());
+        assert_eq!(reader.position(), 10);
+
+        let reader = io::Cursor::new("---\ntitle: Test\n".as_bytes());
+        assert!(read_header(reader, 1024).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_header_limit() {
+        let content = format!("---\ndata: {}\n---\n", "x".repeat(100));
+        let err = read_header(io::Cursor::new(content.as_bytes()), 64).unwrap_err();
+        assert!(matches!(err, FrontmatterError::TooLarge { limit: 64 }));
+
+        let header = read_header(io::Cursor::new(content.as_bytes()), content.len()).unwrap();
+        assert_eq!(header.as_deref(), Some(content.as_str()));
+
+        let unclosed = format!("---\n{}", "x: 1\n".repeat(MAX_HEADER_BYTES / 4));
+        assert!(matches!(
+            read_header(io::Cursor::new(unclosed.as_bytes()), MAX_HEADER_BYTES),
+            Err(FrontmatterError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            split_document(unclosed, None),
+            Err(FrontmatterError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_selected_keys() {
+        let yaml =
+            "Status: active\nauthor:\n  name: Ann\nbig: [1, 2, {a: b}]\nother: !tag x\nÉtat: ok\n";
+        let keys = [
+            "status".to_string(),
+            "author".to_string(),
+            "état".to_string(),
+        ];
+        let fm = parse_yaml(yaml, Some(&keys), 1).unwrap();
+        assert_eq!(
+            fm,
+            serde_yaml::from_str::<Value>("Status: active\nauthor:\n  name: Ann\nÉtat: ok")
+                .unwrap()
+        );
+
+        assert_eq!(
+            parse_yaml("just a string", Some(&keys), 1).unwrap(),
+            Value::from("just a string")
+        );
+        assert!(matches!(
+            parse_yaml("status: [open", Some(&keys), 1),
+            Err(FrontmatterError::Yaml { .. })
+        ));
+    }
+
     #[test]
     fn test_no_frontmatter() {
         let content = "Just body content";
-        assert!(extract_and_parse(content).is_none());
+        assert!(extract_and_parse(content, None).unwrap().is_none());
     }
 }
@@ -0,0 +1,161 @@
+use crate::link::{extract_links, links_in_text, unquoted_wikilink, Link, LinkResolver};
+use crate::note::Note;
+use serde_yaml::Value as YamlValue;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub struct LinkRef {
+    pub line: Option<usize>,
+    pub link: Link,
+}
+
+pub fn note_links(note: &Note) -> Vec<LinkRef> {
+    let mut links = Vec::new();
+    collect_property_links(&note.properties, &mut links);
+    let mut refs: Vec<LinkRef> = links
+        .into_iter()
+        .map(|link| LinkRef { line: None, link })
+        .collect();
+
+    if let Some(body) = note.body() {
+        refs.extend(
+            extract_links(&body.text)
+                .into_iter()
+                .map(|(idx, link)| LinkRef {
+                    line: Some(body.first_line + idx + 1),
+                    link,
+                }),
+        );
+    }
+
+    refs
+}
+
+fn collect_property_links(value: &YamlValue, out: &mut Vec<Link>) {
+    let links = match value {
+        YamlValue::String(s) => links_in_text(s),
+        YamlValue::Sequence(items) => match unquoted_wikilink(value) {
+            Some(s) => links_in_text(&s),
+            None => {
+                items
+                    .iter()
+                    .for_each(|item| collect_property_links(item, out));
+                return;
+            }
+        },
+        YamlValue::Mapping(mapping) => {
+            mapping
+                .values()
+                .for_each(|item| collect_property_links(item, out));
+            return;
+        }
+        _ => return,
+    };
+
+    out.extend(links);
+}
+
+#[derive(Default)]
+pub struct LinkGraph {
+    outlinks: HashMap<PathBuf, Vec<PathBuf>>,
+    inlinks: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl LinkGraph {
+    pub fn build(notes: &[Note], resolver: &LinkResolver) -> Self {
+        let mut graph = LinkGraph::default();
+
+        for note in notes {
+            let source = note.rel_path().to_path_buf();
+            let mut targets: Vec<PathBuf> = note_links(note)
+                .iter()
+                .filter_map(|r| resolver.resolve(&r.link.target))
+                .filter(|t| *t != source)
+                .map(Path::to_path_buf)
+                .collect();
+            targets.sort();
+            targets.dedup();
+
+            for target in &targets {
+                graph
+                    .inlinks
+                    .entry(target.clone())
+                    .or_default()
+                    .push(source.clone());
+            }
+            graph.outlinks.insert(source, targets);
+        }
+
+        for sources in graph.inlinks.values_mut() {
+            sources.sort();
+        }
+
+        graph
+    }
+
+    pub fn outlinks(&self, note: &Path) -> &[PathBuf] {
+        self.outlinks.get(note).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn inlinks(&self, note: &Path) -> &[PathBuf] {
+        self.inlinks.get(note).map_or(&[], Vec::as_slice)
+    }
+}
+
+pub fn as_wikilinks(paths: &[PathBuf]) -> YamlValue {
+    paths
+        .iter()
+        .map(|p| {
+            let path = p.with_extension("");
+            YamlValue::from(format!("[[{}]]", path.to_string_lossy()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(rel_path: &str, yaml: &str, body: &str) -> Note {
+        Note::new(PathBuf::from(rel_path), serde_yaml::from_str(yaml).unwrap())
+            .with_file_info(Path::new(rel_path))
+            .with_body(crate::note::Body {
+                text: body.to_string(),
+                first_line: 0,
+            })
+    }
+
+    #[test]
+    fn test_build_graph() {
+        let notes = vec![
+            note("Projects/Graph.md", "status: active", "Links to [[Ideas]]"),
+            note(
+                "Meetings/m1.md",
+                "project: \"[[Graph]]\"",
+                "[[Graph#Goals]] and [[Missing]] and [[m1]]",
+            ),
+            note("Ideas.md", "related: [[Meetings/m1]]", ""),
+        ];
+        let resolver = LinkResolver::new(notes.iter().map(|n| n.rel_path()));
+        let graph = LinkGraph::build(&notes, &resolver);
+
+        assert_eq!(
+            graph.outlinks(Path::new("Meetings/m1.md")),
+            &[PathBuf::from("Projects/Graph.md")]
+        );
+        assert_eq!(
+            graph.inlinks(Path::new("Meetings/m1.md")),
+            &[PathBuf::from("Ideas.md")]
+        );
+        assert_eq!(
+            graph.inlinks(Path::new("Ideas.md")),
+            &[PathBuf::from("Projects/Graph.md")]
+        );
+        assert!(graph.outlinks(Path::new("Nope.md")).is_empty());
+
+        assert_eq!(
+            as_wikilinks(graph.inlinks(Path::new("Projects/Graph.md"))),
+            YamlValue::from(vec!["[[Meetings/m1]]"])
+        );
+    }
+}
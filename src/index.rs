@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u32,
+}
+
+impl Stamp {
+    pub fn of(path: &Path) -> Option<Stamp> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Stamp {
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    stamp: Stamp,
+    frontmatter: Option<YamlValue>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Index {
+    pub fn load(path: &Path) -> Index {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_yaml::from_str::<Index>(&content).ok())
+            .filter(|index| index.version == VERSION)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&mut self, path: &Path) -> io::Result<()> {
+        self.version = VERSION;
+        let content = serde_yaml::to_string(self).map_err(io::Error::other)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, rel_path: &Path, stamp: Stamp) -> Option<&Option<YamlValue>> {
+        self.entries
+            .get(&key(rel_path))
+            .filter(|e| e.stamp == stamp)
+            .map(|e| &e.frontmatter)
+    }
+
+    pub fn is_fresh(&self, rel_path: &Path, path: &Path) -> Option<bool> {
+        let entry = self.entries.get(&key(rel_path))?;
+        Some(Stamp::of(path) == Some(entry.stamp))
+    }
+
+    pub fn insert(&mut self, rel_path: &Path, stamp: Stamp, frontmatter: Option<YamlValue>) {
+        self.entries
+            .insert(key(rel_path), Entry { stamp, frontmatter });
+    }
+
+    pub fn retain(&mut self, rel_paths: &[&Path]) -> bool {
+        let keep: std::collections::HashSet<String> = rel_paths.iter().map(|p| key(p)).collect();
+        let before = self.entries.len();
+        self.entries.retain(|k, _| keep.contains(k));
+        self.entries.len() != before
+    }
+}
+
+fn key(rel_path: &Path) -> String {
+    rel_path.to_string_lossy().replace('\\', "/")
+}
+
+pub fn location(vault_path: &Path) -> Option<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    let vault = fs::canonicalize(vault_path).unwrap_or_else(|_| vault_path.to_path_buf());
+    let name: String = vault
+        .to_string_lossy()
+        .chars()
+        .map(|c| match c {
+            '%' => "%25".to_string(),
+            '/' | '\\' => "%2F".to_string(),
+            ':' => "%3A".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+
+    Some(cache_dir.join("ovq").join(format!("{}.yaml", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn test_index_roundtrip_and_invalidation() {
+        let dir = TempDir::new("index");
+        let note = dir.write("a.md", "---\nstatus: done\n---\n");
+
+        let stamp = Stamp::of(&note).unwrap();
+        let mut index = Index::default();
+        index.insert(
+            Path::new("a.md"),
+            stamp,
+            serde_yaml::from_str("status: done").ok(),
+        );
+        index.insert(Path::new("gone.md"), stamp, None);
+        assert!(index.retain(&[Path::new("a.md")]));
+
+        let index_path = dir.path().join("cache").join("index.yaml");
+        index.save(&index_path).unwrap();
+        let loaded = Index::load(&index_path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded
+                .get(Path::new("a.md"), stamp)
+                .unwrap()
+                .as_ref()
+                .unwrap()["status"],
+            "done"
+        );
+        assert_eq!(loaded.is_fresh(Path::new("a.md"), &note), Some(true));
+
+        fs::write(&note, "---\nstatus: changed\n---\n").unwrap();
+        let changed = Stamp::of(&note).unwrap();
+        assert!(loaded.get(Path::new("a.md"), changed).is_none());
+        assert_eq!(loaded.is_fresh(Path::new("a.md"), &note), Some(false));
+
+        fs::write(&index_path, "not: [valid").unwrap();
+        assert_eq!(Index::load(&index_path).len(), 0);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::markdown::{body_lines, strip_inline_code};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Precedence {
+    Frontmatter,
+    Inline,
+    Merge,
+}
+
+pub fn extract_inline_fields(body: &str) -> Vec<(String, YamlValue)> {
+    let mut fields = Vec::new();
+
+    for (_, line) in body_lines(body) {
+        let line = strip_inline_code(line);
+        let bracketed = bracketed_fields(&line);
+        if bracketed.is_empty() {
+            fields.extend(full_line_field(&line));
+        } else {
+            fields.extend(bracketed);
+        }
+    }
+
+    fields
+}
+
+fn full_line_field(line: &str) -> Option<(String, YamlValue)> {
+    let content = strip_line_prefix(line);
+    let (key, value) = content.split_once("::")?;
+    let key = normalize_key(key)?;
+    Some((key, parse_value(value)))
+}
+
+fn strip_line_prefix(line: &str) -> &str {
+    let mut s = line.trim_start();
+    while let Some(rest) = s.strip_prefix('>') {
+        s = rest.trim_start();
+    }
+
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = s.strip_prefix(marker) {
+            s = rest.trim_start();
+            break;
+        }
+    }
+    if let Some((num, rest)) = s.split_once(". ") {
+        if !num.is_empty() && num.chars().all(|c| c.is_ascii_digit()) {
+            s = rest.trim_start();
+        }
+    }
+
+    if s.len() >= 4 && s.starts_with('[') && s.as_bytes()[2] == b']' && s.is_char_boundary(3) {
+        s = s[3..].trim_start();
+    }
+
+    s
+}
+
+fn bracketed_fields(line: &str) -> Vec<(String, YamlValue)> {
+    let mut fields = Vec::new();
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let close = match bytes[i] {
+            b'[' => b']',
+            b'(' => b')',
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        if bytes[i] == b'[' && bytes.get(i + 1) == Some(&b'[') {
+            i += 2;
+            continue;
+        }
+
+        let Some(end) = matching_close(bytes, i, bytes[i], close) else {
+            i += 1;
+            continue;
+        };
+
+        let inner = &line[i + 1..end];
+        match inner
+            .split_once("::")
+            .and_then(|(k, v)| Some((normalize_key(k)?, v)))
+        {
+            Some((key, value)) => {
+                fields.push((key, parse_value(value)));
+                i = end + 1;
+            }
+            None => i += 1,
+        }
+    }
+
+    fields
+}
+
+fn matching_close(bytes: &[u8], start: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, &b) in bytes[start..].iter().enumerate() {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(start + offset);
+            }
+        }
+    }
+    None
+}
+
+fn normalize_key(key: &str) -> Option<String> {
+    let key = key.trim().trim_matches('*').trim_matches('_').trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '/' || c == ' ')
+    {
+        return None;
+    }
+    Some(key.split_whitespace().collect::<Vec<_>>().join("-"))
+}
+
+fn parse_value(value: &str) -> YamlValue {
+    let value = value.trim();
+    if value.is_empty() {
+        return YamlValue::Null;
+    }
+    if value.eq_ignore_ascii_case("true") {
+        return YamlValue::Bool(true);
+    }
+    if value.eq_ignore_ascii_case("false") {
+        return YamlValue::Bool(false);
+    }
+    if let Ok(i) = value.parse::<i64>() {
+        return YamlValue::Number(i.into());
+    }
+    if let Ok(f) = value.parse::<f64>() {
+        if f.is_finite() {
+            return YamlValue::Number(f.into());
+        }
+    }
+    YamlValue::String(value.to_string())
+}
+
+pub fn merge_inline_fields(
+    properties: &mut YamlValue,
+    fields: Vec<(String, YamlValue)>,
+    precedence: Precedence,
+) {
+    if !properties.is_mapping() {
+        *properties = YamlValue::Mapping(Mapping::new());
+    }
+    let Some(mapping) = properties.as_mapping_mut() else {
+        return;
+    };
+
+    let mut inline = Mapping::new();
+    for (key, value) in fields {
+        let existing = find_key(&inline, &key).unwrap_or(YamlValue::String(key));
+        match inline.get_mut(&existing) {
+            Some(current) => append(current, value),
+            None => {
+                inline.insert(existing, value);
+            }
+        }
+    }
+
+    for (key, value) in inline {
+        let Some(existing) = key.as_str().and_then(|k| find_key(mapping, k)) else {
+            mapping.insert(key, value);
+            continue;
+        };
+
+        match precedence {
+            Precedence::Frontmatter => {}
+            Precedence::Inline => {
+                mapping.insert(existing, value);
+            }
+            Precedence::Merge => {
+                if let Some(current) = mapping.get_mut(&existing) {
+                    append(current, value);
+                }
+            }
+        }
+    }
+}
+
+fn find_key(mapping: &Mapping, key: &str) -> Option<YamlValue> {
+    mapping
+        .keys()
+        .find(|k| k.as_str().is_some_and(|k| k.eq_ignore_ascii_case(key)))
+        .cloned()
+}
+
+fn append(current: &mut YamlValue, value: YamlValue) {
+    if !current.is_sequence() {
+        let first = std::mem::take(current);
+        *current = YamlValue::Sequence(vec![first]);
+    }
+    if let YamlValue::Sequence(items) = current {
+        match value {
+            YamlValue::Sequence(more) => items.extend(more),
+            other => items.push(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_yaml::from_str;
+
+    #[test]
+    fn test_extract_full_line_and_bracketed() {
+        let body = "status:: active\n- **Due Date**:: 2024-05-01\nMeeting [rating:: 4] and (owner:: [[Alice]])\n`code:: no`\n```\nfenced:: no\n```";
+        let fields = extract_inline_fields(body);
+        assert_eq!(
+            fields,
+            vec![
+                ("status".to_string(), YamlValue::from("active")),
+                ("Due-Date".to_string(), YamlValue::from("2024-05-01")),
+                ("rating".to_string(), YamlValue::from(4)),
+                ("owner".to_string(), YamlValue::from("[[Alice]]")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignores_links_and_urls() {
+        let body = "See [[Note]] and https://example.com/a::b\n- [ ] task [due:: 2024-01-01]";
+        let fields = extract_inline_fields(body);
+        assert_eq!(
+            fields,
+            vec![("due".to_string(), YamlValue::from("2024-01-01"))]
+        );
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let fields = || {
+            vec![
+                ("Status".to_string(), YamlValue::from("inline")),
+                ("tag".to_string(), YamlValue::from("a")),
+                ("tag".to_string(), YamlValue::from("b")),
+            ]
+        };
+
+        let mut fm: YamlValue = from_str("status: fm").unwrap();
+        merge_inline_fields(&mut fm, fields(), Precedence::Frontmatter);
+        assert_eq!(fm["status"], "fm");
+        assert_eq!(fm["tag"], from_str::<YamlValue>("[a, b]").unwrap());
+
+        let mut fm: YamlValue = from_str("status: fm").unwrap();
+        merge_inline_fields(&mut fm, fields(), Precedence::Inline);
+        assert_eq!(fm["status"], "inline");
+
+        let mut fm: YamlValue = from_str("status: fm").unwrap();
+        merge_inline_fields(&mut fm, fields(), Precedence::Merge);
+        assert_eq!(fm["status"], from_str::<YamlValue>("[fm, inline]").unwrap());
+
+        let mut fm = YamlValue::Null;
+        merge_inline_fields(&mut fm, fields(), Precedence::Frontmatter);
+        assert_eq!(fm["Status"], "inline");
+    }
+}
@@ -0,0 +1,380 @@
+use crate::markdown::{body_lines, strip_inline_code};
+use regex::Regex;
+use serde_yaml::Value as YamlValue;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static WIKILINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!?\[\[[^\[\]]+\]\]").unwrap());
+static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[[^\[\]]*\]\((?:<[^>]+>|[^()\s]+)\)").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub text: String,
+    pub target: String,
+    pub subpath: Option<String>,
+    pub display: Option<String>,
+}
+
+impl Link {
+    pub fn parse(s: &str) -> Option<Link> {
+        let text = s.trim();
+        let s = text.strip_prefix('!').unwrap_or(text);
+
+        if let Some(inner) = s.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+            return Some(parse_wikilink(text, inner));
+        }
+
+        parse_markdown_link(text, s)
+    }
+}
+
+pub fn extract_links(body: &str) -> Vec<(usize, Link)> {
+    let mut links = Vec::new();
+    for (idx, line) in body_lines(body) {
+        let line = strip_inline_code(line);
+        links.extend(links_in_text(&line).into_iter().map(|link| (idx, link)));
+    }
+    links
+}
+
+pub fn links_in_text(text: &str) -> Vec<Link> {
+    let wikilinks = WIKILINK.find_iter(text);
+    let markdown = MARKDOWN_LINK
+        .find_iter(text)
+        .filter(|m| !text[..m.start()].ends_with('['));
+
+    let mut matches: Vec<regex::Match> = wikilinks.chain(markdown).collect();
+    matches.sort_by_key(|m| m.start());
+    matches
+        .into_iter()
+        .filter_map(|m| Link::parse(m.as_str()))
+        .filter(|l| !l.target.is_empty())
+        .collect()
+}
+
+// YAML reads an unquoted `key: [[Note]]` as a nested sequence.
+pub fn unquoted_wikilink(v: &YamlValue) -> Option<String> {
+    let [YamlValue::Sequence(inner)] = v.as_sequence()?.as_slice() else {
+        return None;
+    };
+    let [YamlValue::String(s)] = inner.as_slice() else {
+        return None;
+    };
+    Some(format!("[[{}]]", s))
+}
+
+fn parse_wikilink(text: &str, inner: &str) -> Link {
+    let (rest, display) = match inner.split_once('|') {
+        Some((rest, display)) => (rest, Some(display.trim().to_string())),
+        None => (inner, None),
+    };
+    let (target, subpath) = split_subpath(rest);
+
+    Link {
+        text: text.to_string(),
+        target: target.trim().to_string(),
+        subpath,
+        display,
+    }
+}
+
+fn parse_markdown_link(text: &str, s: &str) -> Option<Link> {
+    let rest = s.strip_prefix('[')?;
+    let close = rest.find("](")?;
+    let display = &rest[..close];
+    let url = rest[close + 2..].strip_suffix(')')?.trim();
+    let url = match url.strip_prefix('<').and_then(|u| u.strip_suffix('>')) {
+        Some(bracketed) => bracketed,
+        None if url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') => return None,
+        None => url,
+    };
+
+    if display.contains(['[', ']'])
+        || url.is_empty()
+        || url.contains("://")
+        || url.starts_with("mailto:")
+    {
+        return None;
+    }
+
+    let decoded = percent_decode(url);
+    let (target, subpath) = split_subpath(&decoded);
+
+    Some(Link {
+        text: text.to_string(),
+        target: target.trim().to_string(),
+        subpath,
+        display: (!display.is_empty()).then(|| display.to_string()),
+    })
+}
+
+fn split_subpath(s: &str) -> (&str, Option<String>) {
+    if let Some((target, sub)) = s.split_once('#') {
+        return (target, Some(sub.trim().to_string()));
+    }
+    if let Some(idx) = s.find('^') {
+        return (&s[..idx], Some(s[idx..].trim().to_string()));
+    }
+    (s, None)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(b) = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(out).unwrap_or_else(|_| s.to_string())
+}
+
+pub fn normalize_target(target: &str) -> String {
+    let t = target.trim().replace('\\', "/");
+    let t = t.trim_start_matches("./").trim_start_matches('/');
+    let t = t.to_lowercase();
+    match t.strip_suffix(".md") {
+        Some(stripped) => stripped.to_string(),
+        None => t,
+    }
+}
+
+pub fn aliases(fm: &YamlValue) -> Vec<String> {
+    let Some(mapping) = fm.as_mapping() else {
+        return Vec::new();
+    };
+
+    let mut result = Vec::new();
+    for (key, value) in mapping {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if !key.eq_ignore_ascii_case("aliases") && !key.eq_ignore_ascii_case("alias") {
+            continue;
+        }
+        match value {
+            YamlValue::String(s) => result.extend(s.split(',').map(|a| a.trim().to_string())),
+            YamlValue::Sequence(items) => result.extend(
+                items
+                    .iter()
+                    .filter_map(|i| i.as_str())
+                    .map(|a| a.trim().to_string()),
+            ),
+            _ => {}
+        }
+    }
+
+    result.retain(|a| !a.is_empty());
+    result
+}
+
+pub struct LinkResolver {
+    notes: Vec<PathBuf>,
+    keys: Vec<String>,
+    by_name: HashMap<String, Vec<usize>>,
+    by_alias: HashMap<String, usize>,
+    extensions: Vec<String>,
+}
+
+impl LinkResolver {
+    pub fn new<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Self {
+        Self::with_extensions(paths, &[])
+    }
+
+    pub fn with_extensions<'a>(
+        paths: impl IntoIterator<Item = &'a Path>,
+        extensions: &[String],
+    ) -> Self {
+        let mut resolver = LinkResolver {
+            notes: Vec::new(),
+            keys: Vec::new(),
+            by_name: HashMap::new(),
+            by_alias: HashMap::new(),
+            extensions: extensions
+                .iter()
+                .map(|e| format!(".{}", e.trim_start_matches('.').to_lowercase()))
+                .filter(|e| e != ".md")
+                .collect(),
+        };
+
+        for path in paths {
+            let key = resolver.key(&path.to_string_lossy());
+            let name = key.rsplit('/').next().unwrap_or(&key).to_string();
+            let idx = resolver.notes.len();
+            resolver.notes.push(path.to_path_buf());
+            resolver.keys.push(key);
+            resolver.by_name.entry(name).or_default().push(idx);
+        }
+
+        resolver
+    }
+
+    pub fn add_alias(&mut self, path: &Path, alias: &str) {
+        let key = self.key(&path.to_string_lossy());
+        let name = key.rsplit('/').next().unwrap_or(&key);
+        let Some(idx) = self
+            .by_name
+            .get(name)
+            .and_then(|c| c.iter().copied().find(|&i| self.keys[i] == key))
+        else {
+            return;
+        };
+
+        self.by_alias
+            .entry(alias.trim().to_lowercase())
+            .or_insert(idx);
+    }
+
+    pub fn resolve(&self, target: &str) -> Option<&Path> {
+        let key = self.key(target);
+        if key.is_empty() {
+            return None;
+        }
+
+        self.resolve_file(&key)
+            .or_else(|| self.by_alias.get(&target.trim().to_lowercase()).copied())
+            .map(|i| self.notes[i].as_path())
+    }
+
+    fn key(&self, target: &str) -> String {
+        let key = normalize_target(target);
+        self.extensions
+            .iter()
+            .find_map(|ext| key.strip_suffix(ext.as_str()))
+            .map_or(key.clone(), str::to_string)
+    }
+
+    fn resolve_file(&self, key: &str) -> Option<usize> {
+        let name = key.rsplit('/').next().unwrap_or(key);
+        let candidates = self.by_name.get(name)?;
+        let suffix = format!("/{}", key);
+
+        candidates
+            .iter()
+            .copied()
+            .filter(|&i| {
+                !key.contains('/') || self.keys[i] == key || self.keys[i].ends_with(&suffix)
+            })
+            .min_by(|&a, &b| {
+                let depth = |i: usize| self.keys[i].matches('/').count();
+                depth(a)
+                    .cmp(&depth(b))
+                    .then_with(|| self.keys[a].cmp(&self.keys[b]))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wikilink_parts() {
+        let link = Link::parse("[[Projects/Graph0mane#Goals|Graph]]").unwrap();
+        assert_eq!(link.target, "Projects/Graph0mane");
+        assert_eq!(link.subpath.as_deref(), Some("Goals"));
+        assert_eq!(link.display.as_deref(), Some("Graph"));
+    }
+
+    #[test]
+    fn test_parse_markdown_link() {
+        let link = Link::parse("[Graph](Projects/Graph%200mane.md#Goals)").unwrap();
+        assert_eq!(link.target, "Projects/Graph 0mane.md");
+        assert_eq!(link.subpath.as_deref(), Some("Goals"));
+        assert_eq!(link.display.as_deref(), Some("Graph"));
+
+        assert!(Link::parse("[site](https://example.com)").is_none());
+        assert!(Link::parse("plain text").is_none());
+        assert!(Link::parse("[a](b) and (c)").is_none());
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let body = "See [[A|alias]] and ![[img.png]]\n`[[code]]` [B](Folder/B.md) [web](https://x.y)\n```\n[[fenced]]\n```\n[[#Local]] [[C#^blk]]";
+        let links: Vec<(usize, String)> = extract_links(body)
+            .into_iter()
+            .map(|(idx, l)| (idx, l.target))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                (0, "A".to_string()),
+                (0, "img.png".to_string()),
+                (1, "Folder/B.md".to_string()),
+                (5, "C".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_shortest_path() {
+        let paths = [
+            Path::new("Archive/2023/Graph0mane.md"),
+            Path::new("Projects/Graph0mane.md"),
+            Path::new("Daily/2024-01-01.md"),
+        ];
+        let resolver = LinkResolver::new(paths);
+
+        assert_eq!(
+            resolver.resolve("Graph0mane"),
+            Some(Path::new("Projects/Graph0mane.md"))
+        );
+        assert_eq!(
+            resolver.resolve("2023/graph0mane.md"),
+            Some(Path::new("Archive/2023/Graph0mane.md"))
+        );
+        assert_eq!(resolver.resolve("Other/Graph0mane"), None);
+        assert_eq!(resolver.resolve("Missing"), None);
+    }
+
+    #[test]
+    fn test_resolve_extra_extensions() {
+        let paths = [Path::new("Reports/Q1.qmd"), Path::new("Boards/Plan.canvas")];
+        let resolver = LinkResolver::with_extensions(paths, &["md".to_string(), "qmd".to_string()]);
+
+        assert_eq!(resolver.resolve("Q1"), Some(Path::new("Reports/Q1.qmd")));
+        assert_eq!(
+            resolver.resolve("q1.qmd"),
+            Some(Path::new("Reports/Q1.qmd"))
+        );
+        assert_eq!(
+            resolver.resolve("Plan.canvas"),
+            Some(Path::new("Boards/Plan.canvas"))
+        );
+        assert_eq!(resolver.resolve("Plan"), None);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let fm: YamlValue = serde_yaml::from_str("Aliases: [GM, \"Graph Mane\"]").unwrap();
+        assert_eq!(aliases(&fm), vec!["GM", "Graph Mane"]);
+
+        let fm: YamlValue = serde_yaml::from_str("alias: GM, Graph").unwrap();
+        assert_eq!(aliases(&fm), vec!["GM", "Graph"]);
+
+        let mut resolver =
+            LinkResolver::new([Path::new("Projects/Graph0mane.md"), Path::new("GM.md")]);
+        resolver.add_alias(Path::new("Projects/Graph0mane.md"), "GM");
+        resolver.add_alias(Path::new("Projects/Graph0mane.md"), "Graph Mane");
+
+        assert_eq!(
+            resolver.resolve("graph mane"),
+            Some(Path::new("Projects/Graph0mane.md"))
+        );
+        assert_eq!(resolver.resolve("GM"), Some(Path::new("GM.md")));
+    }
+}
@@ -1,17 +1,43 @@
-mod frontmatter;
+mod check;
+mod edit;
+mod graph;
+mod index;
+mod inline;
+mod link;
+mod markdown;
+mod note;
+mod output;
 mod query;
+mod source;
+mod tags;
+mod tasks;
+#[cfg(test)]
+mod testutil;
 mod values;
 mod vault;
+mod watch;
+mod yaml_edit;
 
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use note::Note;
+use ovq::frontmatter;
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 #[derive(Parser)]
 #[command(name = "ovq", about = "Query Obsidian vault files by frontmatter properties")]
 struct Cli {
-    #[arg(long, env = "OVQ_VAULT")]
-    vault: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Vault to query (repeatable; OVQ_VAULT may hold a path list)"
+    )]
+    vault: Vec<PathBuf>,
 
     #[arg(long, help = "List unique values for a property")]
     values: Option<String>,
@@ -19,78 +45,690 @@ struct Cli {
     #[arg(long, help = "Show count for each value (use with --values)")]
     count: bool,
 
-    #[arg(long, help = "Read file paths from stdin")]
+    #[arg(
+        long,
+        help = "Read file paths from stdin; relative paths resolve against --vault, then the current directory"
+    )]
     stdin: bool,
 
+    #[arg(
+        short = '0',
+        long,
+        requires = "stdin",
+        help = "Paths on stdin are NUL-separated"
+    )]
+    null: bool,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Only match notes whose body matches a regex"
+    )]
+    grep: Option<String>,
+
+    #[arg(long, help = "Query checkbox tasks instead of notes")]
+    tasks: bool,
+
+    #[arg(long, value_name = "NOTE", help = "List notes that link to NOTE")]
+    backlinks: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Number of worker threads (defaults to the number of CPUs)"
+    )]
+    threads: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Reuse parsed frontmatter from the on-disk index, re-parsing only changed notes"
+    )]
+    cache: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "GLOB",
+        value_parser = parse_glob,
+        help = "Only read notes matching GLOB, relative to the vault (repeatable)"
+    )]
+    include: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "GLOB",
+        value_parser = parse_glob,
+        help = "Skip notes matching GLOB, relative to the vault (repeatable)"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Only report notes inside this vault folder (repeatable)"
+    )]
+    folder: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "EXT",
+        value_delimiter = ',',
+        default_value = "md",
+        help = "File extensions read as notes, comma separated; listed yaml/yml files are notes, not sidecars"
+    )]
+    ext: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "SOURCE",
+        help = "Also read metadata from non-markdown files (repeatable)"
+    )]
+    source: Vec<source::Source>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Include files excluded in Obsidian settings, templates, .obsidian/ and .trash/"
+    )]
+    include_excluded: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Descend into symlinked folders, skipping loops and duplicate notes"
+    )]
+    follow_symlinks: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Terminate result paths with NUL instead of newline"
+    )]
+    print0: bool,
+
+    #[arg(long, global = true, help = "Print absolute result paths")]
+    absolute: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "absolute",
+        help = "Print results as obsidian://open links"
+    )]
+    uri: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print a warning for every note whose frontmatter fails to parse"
+    )]
+    warn_invalid: bool,
+
+    #[arg(
+        long,
+        value_name = "PRECEDENCE",
+        help = "Merge inline `key:: value` fields into properties (frontmatter wins, inline wins, or merge into lists)"
+    )]
+    inline_fields: Option<inline::Precedence>,
+
     #[arg(help = "Query in Dataview WHERE syntax")]
     query: Option<String>,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Report vault problems; exits with 1 when any are found")]
+    Check {
+        #[command(subcommand)]
+        check: Check,
+    },
+    #[command(about = "Re-run a query whenever notes in the vault change")]
+    Watch {
+        #[arg(help = "Query in Dataview WHERE syntax")]
+        query: String,
+
+        #[arg(
+            long,
+            help = "Print added and removed notes as `+ path` / `- path` lines"
+        )]
+        diff: bool,
+
+        #[arg(
+            long,
+            value_name = "COMMAND",
+            help = "Run COMMAND with `sh -c` for every note that starts matching; the note path is passed as $1 and OVQ_NOTE"
+        )]
+        exec: Option<String>,
+    },
+    #[command(about = "Set frontmatter properties on every note matching a query")]
+    Set {
+        #[arg(help = "Query in Dataview WHERE syntax")]
+        query: String,
+
+        #[arg(
+            required = true,
+            value_name = "KEY=VALUE",
+            value_parser = edit::parse_assignment,
+            help = "Properties to set; values are parsed as YAML (numbers, booleans, dates, [lists])"
+        )]
+        assignments: Vec<edit::Edit>,
+
+        #[arg(long, help = "Print a unified diff per note instead of writing")]
+        dry_run: bool,
+    },
+    #[command(about = "Remove frontmatter properties from every note matching a query")]
+    Unset {
+        #[arg(help = "Query in Dataview WHERE syntax")]
+        query: String,
+
+        #[arg(required = true, value_name = "KEY", help = "Properties to remove")]
+        keys: Vec<String>,
+
+        #[arg(long, help = "Print a unified diff per note instead of writing")]
+        dry_run: bool,
+    },
+    #[command(about = "Manage the on-disk frontmatter index used by --cache")]
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+}
+
+#[derive(Subcommand, Clone, Copy)]
+enum IndexAction {
+    #[command(about = "Re-parse every note and rewrite the index")]
+    Rebuild,
+    #[command(about = "Show how many notes are indexed, stale or missing")]
+    Status,
+    #[command(about = "Delete the index")]
+    Clear,
+}
+
+#[derive(Subcommand, Clone, Copy)]
+enum Check {
+    #[command(about = "List links that do not resolve to a note or attachment")]
+    Links,
+    #[command(about = "List notes without inlinks or outlinks")]
+    Orphans,
+    #[command(about = "List notes whose frontmatter cannot be read or parsed")]
+    Frontmatter,
+}
+
 fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    let vault_path = match cli.vault {
-        Some(p) => p,
-        None => {
+    if let Some(threads) = cli.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            eprintln!("Error: Cannot start {} threads: {}", threads, e);
+            return ExitCode::from(2);
+        }
+    }
+
+    let vaults = match vault::open_vaults(vault_paths(&cli)) {
+        Ok(vaults) if vaults.is_empty() => {
             eprintln!("Error: No vault path specified. Use --vault or set OVQ_VAULT");
             return ExitCode::from(2);
         }
+        Ok(vaults) => vaults,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::from(2);
+        }
     };
+    if cli.stdin && vaults.len() > 1 {
+        eprintln!("Error: --stdin takes a single vault");
+        return ExitCode::from(2);
+    }
 
-    let files = if cli.stdin {
-        vault::read_paths_from_stdin()
-    } else {
-        vault::collect_markdown_files(&vault_path)
+    match &cli.command {
+        Some(Command::Check { check }) => return run_check(*check, &cli, &vaults),
+        Some(Command::Watch { query, diff, exec }) => {
+            let [vault] = vaults.as_slice() else {
+                eprintln!("Error: watch takes a single vault");
+                return ExitCode::from(2);
+            };
+            return run_watch(&cli, vault, query, *diff, exec.as_deref());
+        }
+        Some(Command::Index { action }) => return run_index(*action, &cli, &vaults),
+        Some(Command::Set {
+            query,
+            assignments,
+            dry_run,
+        }) => return run_edit(&cli, &vaults, query, assignments, *dry_run),
+        Some(Command::Unset {
+            query,
+            keys,
+            dry_run,
+        }) => {
+            let edits: Vec<edit::Edit> = keys.iter().cloned().map(edit::Edit::Unset).collect();
+            return run_edit(&cli, &vaults, query, &edits, *dry_run);
+        }
+        None => {}
+    }
+
+    let expr = match (&cli.values, &cli.query) {
+        (None, Some(query_str)) => match query::parse(query_str) {
+            Ok(e) => Some(e),
+            Err(e) => {
+                eprintln!("Query error: {}", e);
+                return ExitCode::from(2);
+            }
+        },
+        _ => None,
     };
 
-    let frontmatters: Vec<(PathBuf, serde_yaml::Value)> = files
-        .into_iter()
-        .filter_map(|path| {
-            let fm = frontmatter::parse_frontmatter(&path)?;
-            Some((path, fm))
+    let expr = match (&cli.values, &cli.grep) {
+        (None, Some(pattern)) => {
+            let pattern = match regex::Regex::new(pattern) {
+                Ok(p) => query::ast::Pattern(p),
+                Err(e) => {
+                    eprintln!("Error: Invalid --grep pattern: {}", e);
+                    return ExitCode::from(2);
+                }
+            };
+            let grep = query::ast::Expr::Matches {
+                field: "content".to_string(),
+                pattern,
+            };
+            Some(match expr {
+                Some(e) => query::ast::Expr::And(Box::new(e), Box::new(grep)),
+                None => grep,
+            })
+        }
+        _ => expr,
+    };
+
+    let options = load_options(&cli, expr.as_ref());
+    let loaded: Vec<_> = vaults
+        .iter()
+        .map(|vault| {
+            let (mut notes, resolver, _, scope) = load_notes(&cli, vault, &options);
+            let link_graph =
+                needs_graph(&cli, expr.as_ref()).then(|| attach_graph(&mut notes, &resolver));
+            notes.retain(|n| scope.contains(n));
+            (vault, notes, resolver, link_graph)
         })
         .collect();
 
-    if let Some(property) = cli.values {
-        return run_values_mode(&frontmatters, &property, cli.count);
+    if let Some(property) = &cli.values {
+        let notes = loaded.iter().flat_map(|(_, notes, _, _)| notes);
+        return run_values_mode(notes, property, cli.count);
     }
 
-    let Some(query_str) = cli.query else {
+    if !cli.tasks && cli.backlinks.is_none() && expr.is_none() {
         eprintln!("Error: No query provided");
         return ExitCode::from(2);
-    };
+    }
 
-    run_query_mode(&frontmatters, &query_str, &vault_path)
+    let mut found = false;
+    let mut target_found = false;
+    for (vault, notes, resolver, link_graph) in &loaded {
+        let ctx = query::Context {
+            links: Some(resolver),
+        };
+        let format = output_format(&cli, vault).labeled(vaults.len() > 1);
+
+        found |= match (&cli.backlinks, link_graph, &expr) {
+            _ if cli.tasks => run_tasks_mode(notes, expr.as_ref(), &ctx, &format),
+            (Some(target), Some(link_graph), _) => {
+                let found =
+                    run_backlinks_mode(notes, link_graph, target, expr.as_ref(), &ctx, &format);
+                target_found |= found.is_some();
+                found.unwrap_or(false)
+            }
+            (_, _, Some(expr)) => run_query_mode(notes, expr, &ctx, &format),
+            _ => false,
+        };
+    }
+
+    if let (Some(target), false, false) = (&cli.backlinks, cli.tasks, target_found) {
+        eprintln!("Error: Note not found: {}", target);
+        return ExitCode::from(2);
+    }
+
+    if found {
+        ExitCode::from(0)
+    } else {
+        ExitCode::from(1)
+    }
 }
 
-fn run_values_mode(
-    frontmatters: &[(PathBuf, serde_yaml::Value)],
-    property: &str,
-    show_count: bool,
-) -> ExitCode {
-    let data: Vec<(String, serde_yaml::Value)> = frontmatters
+fn vault_paths(cli: &Cli) -> Vec<PathBuf> {
+    if !cli.vault.is_empty() {
+        return cli.vault.clone();
+    }
+    match std::env::var_os("OVQ_VAULT").filter(|v| !v.is_empty()) {
+        Some(list) => std::env::split_paths(&list)
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect(),
+        None if cli.stdin => {
+            vec![std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))]
+        }
+        None => Vec::new(),
+    }
+}
+
+fn needs_graph(cli: &Cli, expr: Option<&query::ast::Expr>) -> bool {
+    cli.backlinks.is_some()
+        || expr.is_some_and(|e| references_file_fields(e, &["outlinks", "inlinks"]))
+}
+
+fn load_options(cli: &Cli, expr: Option<&query::ast::Expr>) -> LoadOptions {
+    let references = |names: &[&str]| expr.is_some_and(|e| references_file_fields(e, names));
+    let needs_graph = needs_graph(cli, expr);
+
+    LoadOptions {
+        inline_fields: cli.inline_fields,
+        body_tags: references(&["tags", "etags"]),
+        structure: references(&["headings", "sections", "blocks"]),
+        with_body: cli.tasks || needs_graph,
+        cache: cli.cache,
+        keys: if needs_graph || cli.cache {
+            None
+        } else {
+            Some(property_keys(match (&cli.values, expr) {
+                (Some(property), _) => vec![property.as_str()],
+                (None, Some(expr)) => expr.fields(),
+                (None, None) => Vec::new(),
+            }))
+        },
+        required: match (&cli.values, expr) {
+            (Some(property), _) => vec![property.clone()],
+            (None, Some(expr)) if !cli.tasks && !needs_graph => expr
+                .required_fields()
+                .into_iter()
+                .filter(|f| note::split_file_field(f).is_none() && !note::is_body_field(f))
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn attach_graph(notes: &mut [Note], resolver: &link::LinkResolver) -> graph::LinkGraph {
+    let link_graph = graph::LinkGraph::build(notes, resolver);
+    for note in notes {
+        let rel_path = note.rel_path().to_path_buf();
+        note.set_file_field(
+            "outlinks",
+            graph::as_wikilinks(link_graph.outlinks(&rel_path)),
+        );
+        note.set_file_field(
+            "inlinks",
+            graph::as_wikilinks(link_graph.inlinks(&rel_path)),
+        );
+    }
+    link_graph
+}
+
+fn property_keys(fields: Vec<&str>) -> Vec<String> {
+    let mut keys: Vec<String> = ["tags", "tag", "aliases", "alias"]
+        .map(String::from)
+        .to_vec();
+    for field in fields {
+        if note::split_file_field(field).is_some() || note::is_body_field(field) {
+            continue;
+        }
+        keys.push(field.to_lowercase());
+        if let Some((head, _)) = field.split_once('.') {
+            keys.push(head.to_lowercase());
+        }
+    }
+    keys
+}
+
+fn references_file_fields(expr: &query::ast::Expr, names: &[&str]) -> bool {
+    expr.fields()
         .iter()
-        .map(|(p, fm)| (p.display().to_string(), fm.clone()))
-        .collect();
+        .filter_map(|f| note::split_file_field(f))
+        .map(|k| k.split('.').next().unwrap_or(k))
+        .any(|k| names.iter().any(|n| n.eq_ignore_ascii_case(k)))
+}
 
-    let counts = values::collect_values(&data, property);
+fn run_check(check: Check, cli: &Cli, vaults: &[vault::Vault]) -> ExitCode {
+    let problems: usize = vaults
+        .iter()
+        .map(|vault| {
+            let format = output_format(cli, vault).labeled(vaults.len() > 1);
+            check_vault(check, cli, vault, &format)
+        })
+        .sum();
 
-    if counts.is_empty() {
-        return ExitCode::from(1);
+    if problems == 0 {
+        ExitCode::from(0)
+    } else {
+        ExitCode::from(1)
     }
+}
 
-    let lines = values::format_values(counts, show_count);
-    for line in lines {
-        println!("{}", line);
+fn check_vault(check: Check, cli: &Cli, vault: &vault::Vault, format: &output::Format) -> usize {
+    let vault_path = &vault.path;
+    let options = LoadOptions {
+        inline_fields: cli.inline_fields,
+        body_tags: false,
+        structure: false,
+        with_body: !matches!(check, Check::Frontmatter),
+        cache: cli.cache,
+        keys: None,
+        required: Vec::new(),
+    };
+    let (notes, resolver, invalid, scope) = load_notes(cli, vault, &options);
+
+    match check {
+        Check::Links => {
+            let attachments = link::LinkResolver::new(
+                vault::collect_attachments(vault_path, &link_filter(cli))
+                    .iter()
+                    .map(|p| p.strip_prefix(vault_path).unwrap_or(p)),
+            );
+            let mut broken = check::broken_links(&notes, &resolver, &attachments);
+            broken.retain(|b| scope.contains(b.note));
+            for b in &broken {
+                format.print_location(b.note.rel_path(), b.line, &b.link.text);
+            }
+            broken.len()
+        }
+        Check::Orphans => {
+            let link_graph = graph::LinkGraph::build(&notes, &resolver);
+            let mut orphans = check::orphans(&notes, &link_graph);
+            orphans.retain(|n| scope.contains(n));
+            for note in &orphans {
+                format.print_path(note.rel_path());
+            }
+            orphans.len()
+        }
+        Check::Frontmatter => {
+            for note in &invalid {
+                let rel_path = note.path.strip_prefix(vault_path).unwrap_or(&note.path);
+                let message = note.error.to_string();
+                match note.error.line() {
+                    Some(line) => format.print_location(rel_path, line, &message),
+                    None => format.print_message(rel_path, &message),
+                }
+            }
+            invalid.len()
+        }
     }
+}
 
-    ExitCode::from(0)
+fn run_watch(
+    cli: &Cli,
+    vault: &vault::Vault,
+    query_str: &str,
+    diff: bool,
+    exec: Option<&str>,
+) -> ExitCode {
+    let expr = match query::parse(query_str) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("Query error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let mut options = load_options(cli, Some(&expr));
+    options.cache = false;
+    options.required.clear();
+    let needs_graph = needs_graph(cli, Some(&expr));
+
+    let vault_path = &vault.path;
+    let filter = link_filter(cli);
+    let format = output_format(cli, vault);
+    let watcher = match watch::VaultWatcher::new(vault_path) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("Error: Cannot watch {}: {}", vault_path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut notes = Vec::new();
+    let mut changed = BTreeSet::new();
+    let mut matches: Option<BTreeSet<PathBuf>> = None;
+
+    loop {
+        let (next, resolver, scope) = refresh_notes(cli, vault, &options, notes, &changed);
+        notes = next;
+        if needs_graph {
+            attach_graph(&mut notes, &resolver);
+        }
+
+        let ctx = query::Context {
+            links: Some(&resolver),
+        };
+        let current: BTreeSet<PathBuf> = notes
+            .par_iter()
+            .filter(|note| scope.contains(note))
+            .filter(|note| query::evaluate(&expr, note, &ctx))
+            .map(|note| note.rel_path().to_path_buf())
+            .collect();
+
+        let empty = BTreeSet::new();
+        let previous = matches.as_ref().unwrap_or(&empty);
+        let changes = watch::diff(previous, &current);
+
+        if diff {
+            changes.iter().for_each(|c| format.print_change(c));
+        } else if matches.is_none() || !changes.is_empty() {
+            output::clear_screen();
+            current.iter().for_each(|p| format.print_path(p));
+        }
+
+        if let (Some(command), Some(_)) = (exec, &matches) {
+            for change in &changes {
+                let watch::Change::Added(note) = change else {
+                    continue;
+                };
+                match watch::run_hook(command, vault_path, note) {
+                    Ok(status) if !status.success() => {
+                        eprintln!("Warning: --exec failed for {}: {}", note.display(), status)
+                    }
+                    Err(e) => eprintln!("Warning: --exec failed for {}: {}", note.display(), e),
+                    Ok(_) => {}
+                }
+            }
+        }
+        matches = Some(current);
+
+        changed = loop {
+            match watcher.next_batch() {
+                Ok(paths)
+                    if paths
+                        .iter()
+                        .any(|p| filter.is_note(p) || p.extension().is_none()) =>
+                {
+                    break paths
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    eprintln!("Error: Watch failed: {}", e);
+                    return ExitCode::from(2);
+                }
+            }
+        };
+    }
 }
 
-fn run_query_mode(
-    frontmatters: &[(PathBuf, serde_yaml::Value)],
+fn refresh_notes(
+    cli: &Cli,
+    vault: &vault::Vault,
+    options: &LoadOptions,
+    previous: Vec<Note>,
+    changed: &BTreeSet<PathBuf>,
+) -> (Vec<Note>, link::LinkResolver, Scope) {
+    let vault_path = &vault.path;
+    let (files, scope) = collect_vault_notes(cli, vault_path);
+    let changed = in_vault(vault_path, changed);
+    let mut previous: HashMap<PathBuf, Note> = previous
+        .into_iter()
+        .filter(|n| !changed.contains(&n.path))
+        .map(|n| (n.path.clone(), n))
+        .collect();
+
+    let (kept, stale): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        files.iter().partition(|p| previous.contains_key(*p));
+    let mut notes: Vec<Note> = kept
+        .into_iter()
+        .filter_map(|p| previous.remove(p))
+        .collect();
+    notes.par_extend(stale.into_par_iter().filter_map(|path| {
+        match load_note(
+            path,
+            source::Source::of(path, &cli.ext),
+            vault,
+            options,
+            None,
+        ) {
+            Ok(note) => note,
+            Err(error) => {
+                let rel_path = path.strip_prefix(vault_path).unwrap_or(path);
+                if cli.warn_invalid && scope.contains_path(path, rel_path) {
+                    eprintln!("Warning: {}: {}", rel_path.display(), error);
+                }
+                None
+            }
+        }
+    }));
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let resolver = build_resolver(&files, vault_path, &notes, &cli.ext);
+    (notes, resolver, scope)
+}
+
+// Watcher events carry absolute paths, while note paths keep `--vault` as it was typed.
+fn in_vault(vault_path: &Path, paths: &BTreeSet<PathBuf>) -> BTreeSet<PathBuf> {
+    let roots: Vec<PathBuf> = [
+        std::path::absolute(vault_path).ok(),
+        std::fs::canonicalize(vault_path).ok(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    paths
+        .iter()
+        .map(|path| {
+            roots
+                .iter()
+                .find_map(|root| path.strip_prefix(root).ok())
+                .map_or_else(|| path.clone(), |rel_path| vault_path.join(rel_path))
+        })
+        .collect()
+}
+
+fn run_edit(
+    cli: &Cli,
+    vaults: &[vault::Vault],
     query_str: &str,
-    vault_path: &PathBuf,
+    edits: &[edit::Edit],
+    dry_run: bool,
 ) -> ExitCode {
     let expr = match query::parse(query_str) {
         Ok(e) => e,
@@ -99,23 +737,564 @@ fn run_query_mode(
             return ExitCode::from(2);
         }
     };
+    let mut options = load_options(cli, Some(&expr));
+    options.with_body = true;
 
     let mut found = false;
+    let mut failed = false;
+    for vault in vaults {
+        let (mut notes, resolver, _, scope) = load_notes(cli, vault, &options);
+        if needs_graph(cli, Some(&expr)) {
+            attach_graph(&mut notes, &resolver);
+        }
+        let ctx = query::Context {
+            links: Some(&resolver),
+        };
+        let format = output_format(cli, vault).labeled(vaults.len() > 1);
 
-    for (path, fm) in frontmatters {
-        if query::evaluate(&expr, fm) {
+        let matched = notes
+            .iter()
+            .filter(|n| scope.contains(n) && query::evaluate(&expr, n, &ctx));
+        for note in matched {
             found = true;
-            let display_path = path
-                .strip_prefix(vault_path)
-                .unwrap_or(path)
-                .display();
-            println!("{}", display_path);
+            let rel_path = note.rel_path();
+            if source::Source::of(&note.path, &cli.ext) != source::Source::Markdown {
+                eprintln!(
+                    "Warning: Skipping {}: only Markdown notes can be edited",
+                    rel_path.display()
+                );
+                continue;
+            }
+
+            let edited = frontmatter::read_file(&note.path)
+                .map_err(edit::EditError::from)
+                .and_then(|content| Ok((edit::apply(&content, edits)?, content)));
+            let (updated, content) = match edited {
+                Ok((updated, content)) if updated != content => (updated, content),
+                Ok(_) => continue,
+                Err(e) => {
+                    eprintln!("Error: {}: {}", rel_path.display(), e);
+                    failed = true;
+                    continue;
+                }
+            };
+
+            if dry_run {
+                print!(
+                    "{}",
+                    edit::unified_diff(&format.render(rel_path), &content, &updated)
+                );
+            } else if let Err(e) = edit::write_note(&note.path, &updated) {
+                eprintln!("Error: Cannot write {}: {}", rel_path.display(), e);
+                failed = true;
+            } else {
+                format.print_path(rel_path);
+            }
         }
     }
 
-    if found {
+    if failed {
+        ExitCode::from(2)
+    } else if found {
         ExitCode::from(0)
     } else {
         ExitCode::from(1)
     }
 }
+
+fn run_index(action: IndexAction, cli: &Cli, vaults: &[vault::Vault]) -> ExitCode {
+    for vault in vaults {
+        let code = index_vault(action, cli, vault);
+        if code != ExitCode::SUCCESS {
+            return code;
+        }
+    }
+    ExitCode::from(0)
+}
+
+fn index_vault(action: IndexAction, cli: &Cli, vault: &vault::Vault) -> ExitCode {
+    let vault_path = &vault.path;
+    let Some(index_path) = index::location(vault_path) else {
+        eprintln!("Error: Cannot determine cache directory; set XDG_CACHE_HOME or HOME");
+        return ExitCode::from(2);
+    };
+
+    match action {
+        IndexAction::Rebuild => {
+            if let Err(e) = clear_index(&index_path) {
+                eprintln!("Error: Cannot remove {}: {}", index_path.display(), e);
+                return ExitCode::from(2);
+            }
+            let options = LoadOptions {
+                inline_fields: None,
+                body_tags: false,
+                structure: false,
+                with_body: false,
+                cache: true,
+                keys: None,
+                required: Vec::new(),
+            };
+            load_notes(cli, vault, &options);
+            let index = index::Index::load(&index_path);
+            println!("Indexed {} notes in {}", index.len(), index_path.display());
+        }
+        IndexAction::Status => {
+            let index = index::Index::load(&index_path);
+            let (mut fresh, mut stale, mut missing) = (0, 0, 0);
+            for path in vault::collect_notes(vault_path, &link_filter(cli)) {
+                let rel_path = path.strip_prefix(vault_path).unwrap_or(&path);
+                match index.is_fresh(rel_path, &path) {
+                    Some(true) => fresh += 1,
+                    Some(false) => stale += 1,
+                    None => missing += 1,
+                }
+            }
+            println!("index: {}", index_path.display());
+            println!("entries: {}", index.len());
+            println!("fresh: {}", fresh);
+            println!("stale: {}", stale);
+            println!("missing: {}", missing);
+        }
+        IndexAction::Clear => {
+            if let Err(e) = clear_index(&index_path) {
+                eprintln!("Error: Cannot remove {}: {}", index_path.display(), e);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    ExitCode::from(0)
+}
+
+fn clear_index(index_path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(index_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+struct InvalidNote {
+    path: PathBuf,
+    error: frontmatter::FrontmatterError,
+}
+
+fn load_notes(
+    cli: &Cli,
+    vault: &vault::Vault,
+    options: &LoadOptions,
+) -> (Vec<Note>, link::LinkResolver, Vec<InvalidNote>, Scope) {
+    let vault_path = &vault.path;
+    let (files, scope) = collect_vault_notes(cli, vault_path);
+
+    let index_path = options
+        .cache
+        .then(|| index::location(vault_path))
+        .flatten()
+        .filter(|_| !options.needs_body());
+    let mut index = index_path.as_deref().map(index::Index::load);
+
+    let loaded: Vec<_> = files
+        .par_iter()
+        .map(|path| {
+            let rel_path = path.strip_prefix(vault_path).unwrap_or(path);
+            let stamp = index.as_ref().and_then(|_| index::Stamp::of(path));
+            let cached = index
+                .as_ref()
+                .zip(stamp)
+                .and_then(|(index, stamp)| index.get(rel_path, stamp).cloned());
+            let fresh = stamp.filter(|_| cached.is_none());
+            let source = source::Source::of(path, &cli.ext);
+            let note = load_note(path, source, vault, options, cached);
+            (note, path.clone(), fresh)
+        })
+        .collect();
+
+    let mut notes = Vec::new();
+    let mut invalid = Vec::new();
+    let mut index_changed = false;
+    for (result, path, fresh) in &loaded {
+        if let (Some(index), Some(stamp), Ok(note)) = (&mut index, fresh, result) {
+            let rel_path = path.strip_prefix(vault_path).unwrap_or(path);
+            index.insert(
+                rel_path,
+                *stamp,
+                note.as_ref().map(|n| n.properties.clone()),
+            );
+            index_changed = true;
+        }
+    }
+
+    if let (Some(index), Some(index_path)) = (&mut index, &index_path) {
+        if !cli.stdin {
+            let rel_paths: Vec<&Path> = loaded
+                .iter()
+                .map(|(_, path, _)| path.strip_prefix(vault_path).unwrap_or(path))
+                .collect();
+            index_changed |= index.retain(&rel_paths);
+        }
+        if index_changed {
+            if let Err(e) = index.save(index_path) {
+                eprintln!(
+                    "Warning: Cannot write index {}: {}",
+                    index_path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    for (result, path, _) in loaded {
+        match result {
+            Ok(Some(note)) => notes.push(note),
+            Ok(None) => {}
+            Err(error) => {
+                let rel_path = path.strip_prefix(vault_path).unwrap_or(&path);
+                if !scope.contains_path(&path, rel_path) {
+                    continue;
+                }
+                if cli.warn_invalid {
+                    eprintln!("Warning: {}: {}", rel_path.display(), error);
+                }
+                invalid.push(InvalidNote { path, error });
+            }
+        }
+    }
+
+    let resolver = build_resolver(&files, vault_path, &notes, &cli.ext);
+    notes.retain(|n| options.required.iter().all(|f| n.get(f).is_some()));
+
+    (notes, resolver, invalid, scope)
+}
+
+// Links resolve against every note in the vault; the scope is the part of it that gets
+// matched and reported.
+fn collect_vault_notes(cli: &Cli, vault_path: &Path) -> (Vec<PathBuf>, Scope) {
+    let (files, scoped) = if cli.stdin {
+        let files = vault::read_paths_from_stdin(vault_path, cli.null);
+        let scoped = files.clone();
+        (files, scoped)
+    } else {
+        (
+            vault::collect_notes(vault_path, &link_filter(cli)),
+            vault::collect_notes(vault_path, &vault_filter(cli, vault_path)),
+        )
+    };
+    let scope = Scope {
+        files: scoped.into_iter().collect(),
+        folders: cli.folder.clone(),
+    };
+    (files, scope)
+}
+
+struct Scope {
+    files: HashSet<PathBuf>,
+    folders: Vec<String>,
+}
+
+impl Scope {
+    fn contains(&self, note: &Note) -> bool {
+        self.contains_path(&note.path, note.rel_path())
+    }
+
+    fn contains_path(&self, path: &Path, rel_path: &Path) -> bool {
+        self.files.contains(path) && vault::in_folders(rel_path, &self.folders)
+    }
+}
+
+fn output_format(cli: &Cli, vault: &vault::Vault) -> output::Format {
+    let style = if cli.uri {
+        output::PathStyle::Uri
+    } else if cli.absolute {
+        output::PathStyle::Absolute
+    } else {
+        output::PathStyle::Relative
+    };
+    output::Format::new(vault, style, cli.print0)
+}
+
+fn vault_filter(cli: &Cli, vault_path: &Path) -> vault::Filter {
+    let filter = if cli.include_excluded {
+        vault::Filter::default()
+    } else {
+        vault::Filter::obsidian(vault_path)
+    };
+    let mut filter = filter
+        .with_sources(&cli.ext, &cli.source)
+        .with_symlinks(cli.follow_symlinks);
+    if let Err(e) = filter.set_globs(vault_path, &cli.include, &cli.exclude) {
+        eprintln!("Warning: Ignoring --include/--exclude: {}", e);
+    }
+    filter
+}
+
+fn link_filter(cli: &Cli) -> vault::Filter {
+    let filter = if cli.include_excluded {
+        vault::Filter::default()
+    } else {
+        vault::Filter::linkable()
+    };
+    filter
+        .with_sources(&cli.ext, &cli.source)
+        .with_symlinks(cli.follow_symlinks)
+}
+
+fn parse_glob(glob: &str) -> Result<String, String> {
+    ignore::overrides::OverrideBuilder::new("")
+        .add(glob)
+        .map(|_| glob.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn build_resolver(
+    files: &[PathBuf],
+    vault_path: &Path,
+    notes: &[Note],
+    extensions: &[String],
+) -> link::LinkResolver {
+    let rel_paths: Vec<PathBuf> = files
+        .iter()
+        .map(|p| {
+            let source = source::Source::of(p, extensions);
+            source::note_path(p.strip_prefix(vault_path).unwrap_or(p), source)
+        })
+        .collect();
+    let mut resolver =
+        link::LinkResolver::with_extensions(rel_paths.iter().map(PathBuf::as_path), extensions);
+    for note in notes {
+        for alias in link::aliases(&note.properties) {
+            resolver.add_alias(note.rel_path(), &alias);
+        }
+    }
+    resolver
+}
+
+struct LoadOptions {
+    inline_fields: Option<inline::Precedence>,
+    body_tags: bool,
+    structure: bool,
+    with_body: bool,
+    cache: bool,
+    keys: Option<Vec<String>>,
+    required: Vec<String>,
+}
+
+impl LoadOptions {
+    fn needs_body(&self) -> bool {
+        self.inline_fields.is_some() || self.body_tags || self.structure || self.with_body
+    }
+}
+
+fn load_note(
+    path: &Path,
+    source: source::Source,
+    vault: &vault::Vault,
+    options: &LoadOptions,
+    cached: Option<Option<serde_yaml::Value>>,
+) -> Result<Option<Note>, frontmatter::FrontmatterError> {
+    let doc = if options.needs_body() {
+        source::parse_document(path, source, options.keys.as_deref())?
+    } else {
+        let frontmatter = match cached {
+            Some(frontmatter) => frontmatter,
+            None => source::parse_frontmatter(path, source, options.keys.as_deref())?,
+        };
+        let Some(frontmatter) = frontmatter else {
+            return Ok(None);
+        };
+        frontmatter::Document {
+            frontmatter: Some(frontmatter),
+            body: String::new(),
+            body_line: 0,
+        }
+    };
+
+    let inline_fields = match options.inline_fields {
+        Some(_) => inline::extract_inline_fields(&doc.body),
+        None => Vec::new(),
+    };
+    let body_tags = if options.body_tags {
+        tags::extract_body_tags(&doc.body)
+    } else {
+        Vec::new()
+    };
+    if doc.frontmatter.is_none()
+        && !options.with_body
+        && !options.structure
+        && inline_fields.is_empty()
+        && body_tags.is_empty()
+    {
+        return Ok(None);
+    }
+
+    let mut properties = doc.frontmatter.unwrap_or_default();
+    if let Some(precedence) = options.inline_fields {
+        inline::merge_inline_fields(&mut properties, inline_fields, precedence);
+    }
+
+    let mut etags = tags::frontmatter_tags(&properties);
+    etags.extend(body_tags);
+    let etags = tags::dedupe(etags);
+
+    let rel_path = source::note_path(path.strip_prefix(&vault.path).unwrap_or(path), source);
+    let mut note = Note::new(path.to_path_buf(), properties).with_file_info(&rel_path);
+    note.set_file_field("vault", vault.name.as_str());
+    note.set_file_field("tags", tags::expand_parents(&etags));
+    note.set_file_field("etags", etags);
+
+    if options.structure {
+        set_structure_fields(&mut note, &doc.body);
+    }
+
+    if options.with_body {
+        note = note.with_body(note::Body {
+            text: doc.body,
+            first_line: doc.body_line,
+        });
+    }
+    Ok(Some(note))
+}
+
+fn set_structure_fields(note: &mut Note, body: &str) {
+    let headings: Vec<String> = markdown::headings(body)
+        .into_iter()
+        .map(|h| h.text)
+        .collect();
+
+    let mut sections = serde_yaml::Mapping::new();
+    for (heading, content) in markdown::sections(body) {
+        let key = serde_yaml::Value::from(markdown::slug(&heading.text));
+        match sections.get_mut(&key) {
+            Some(serde_yaml::Value::String(existing)) => {
+                existing.push('\n');
+                existing.push_str(&content);
+            }
+            _ => {
+                sections.insert(key, content.into());
+            }
+        }
+    }
+
+    note.set_file_field("headings", headings);
+    note.set_file_field("sections", sections);
+    note.set_file_field("blocks", markdown::block_ids(body));
+}
+
+fn run_values_mode<'a>(
+    notes: impl IntoIterator<Item = &'a Note>,
+    property: &str,
+    show_count: bool,
+) -> ExitCode {
+    let counts = values::collect_values(notes.into_iter().map(|n| &n.properties), property);
+
+    if counts.is_empty() {
+        return ExitCode::from(1);
+    }
+
+    let lines = values::format_values(counts, show_count);
+    for line in lines {
+        println!("{}", line);
+    }
+
+    ExitCode::from(0)
+}
+
+fn run_query_mode(
+    notes: &[Note],
+    expr: &query::ast::Expr,
+    ctx: &query::Context,
+    format: &output::Format,
+) -> bool {
+    let matches: Vec<&Note> = notes
+        .par_iter()
+        .filter(|note| query::evaluate(expr, note, ctx))
+        .collect();
+
+    for note in &matches {
+        format.print_path(note.rel_path());
+    }
+
+    !matches.is_empty()
+}
+
+fn run_tasks_mode(
+    notes: &[Note],
+    expr: Option<&query::ast::Expr>,
+    ctx: &query::Context,
+    format: &output::Format,
+) -> bool {
+    let mut found = false;
+
+    for note in notes {
+        let Some(body) = note.body() else {
+            continue;
+        };
+
+        for task in tasks::extract_tasks(&body.text, body.first_line) {
+            let mut task_note = Note::new(note.path.clone(), task.to_properties(&note.properties));
+            task_note.file = note.file.clone();
+
+            if expr.is_none_or(|e| query::evaluate(e, &task_note, ctx)) {
+                found = true;
+                format.print_location(note.rel_path(), task.line, &task.text);
+            }
+        }
+    }
+
+    found
+}
+
+fn run_backlinks_mode(
+    notes: &[Note],
+    link_graph: &graph::LinkGraph,
+    target: &str,
+    expr: Option<&query::ast::Expr>,
+    ctx: &query::Context,
+    format: &output::Format,
+) -> Option<bool> {
+    let target_link = link::Link::parse(target);
+    let target_name = target_link.as_ref().map_or(target, |l| l.target.as_str());
+    let target_path = ctx.links.and_then(|r| r.resolve(target_name))?;
+
+    let mut found = false;
+
+    for source in link_graph.inlinks(target_path) {
+        let Some(note) = notes.iter().find(|n| n.rel_path() == source) else {
+            continue;
+        };
+        if expr.is_none_or(|e| query::evaluate(e, note, ctx)) {
+            found = true;
+            format.print_path(source);
+        }
+    }
+
+    Some(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn test_refresh_notes_relative_vault() {
+        let dir = TempDir::new("refresh");
+        let note = dir.write("Note.md", "---\nstatus: draft\n---\n");
+        let cwd = std::env::current_dir().unwrap();
+        let up = "../".repeat(cwd.components().count() - 1);
+        let vault_path = PathBuf::from(up).join(dir.path().strip_prefix("/").unwrap());
+        let cli = Cli::parse_from(["ovq", "--vault", vault_path.to_str().unwrap()]);
+        let vault = vault::Vault::new(vault_path);
+        let options = LoadOptions {
+            keys: None,
+            ..load_options(&cli, None)
+        };
+
+        let (notes, _, _) = refresh_notes(&cli, &vault, &options, Vec::new(), &BTreeSet::new());
+        assert_eq!(notes[0].get("status").unwrap().as_str(), Some("draft"));
+
+        std::fs::write(&note, "---\nstatus: done\n---\n").unwrap();
+        let changed = BTreeSet::from([note]);
+        let (notes, _, _) = refresh_notes(&cli, &vault, &options, notes, &changed);
+        assert_eq!(notes[0].get("status").unwrap().as_str(), Some("done"));
+    }
+}
@@ -0,0 +1,168 @@
+pub fn body_lines(body: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut fence: Option<&str> = None;
+
+    body.lines().enumerate().filter_map(move |(idx, line)| {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+
+        match (fence, marker) {
+            (None, Some(m)) => {
+                fence = Some(m);
+                None
+            }
+            (Some(open), Some(m)) if open == m => {
+                fence = None;
+                None
+            }
+            (Some(_), _) => None,
+            (None, None) => Some((idx, line)),
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+    pub line: usize,
+}
+
+pub fn headings(body: &str) -> Vec<Heading> {
+    body_lines(body)
+        .filter_map(|(idx, line)| {
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let content = &line[indent..];
+            let level = content.len() - content.trim_start_matches('#').len();
+            let rest = &content[level..];
+
+            if indent > 3 || !(1..=6).contains(&level) {
+                return None;
+            }
+            if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+                return None;
+            }
+
+            let text = rest.trim().trim_end_matches('#').trim_end();
+            Some(Heading {
+                level,
+                text: text.to_string(),
+                line: idx,
+            })
+        })
+        .collect()
+}
+
+pub fn sections(body: &str) -> Vec<(Heading, String)> {
+    let lines: Vec<&str> = body.lines().collect();
+    let all = headings(body);
+
+    all.iter()
+        .enumerate()
+        .map(|(i, heading)| {
+            let end = all[i + 1..]
+                .iter()
+                .find(|h| h.level <= heading.level)
+                .map_or(lines.len(), |h| h.line);
+            let content = lines[heading.line + 1..end].join("\n");
+            (heading.clone(), content)
+        })
+        .collect()
+}
+
+pub fn block_ids(body: &str) -> Vec<String> {
+    body_lines(body)
+        .filter_map(|(_, line)| {
+            let line = line.trim_end();
+            let idx = line.rfind('^')?;
+            let id = &line[idx + 1..];
+            let preceded_ok = line[..idx].is_empty() || line[..idx].ends_with(char::is_whitespace);
+            let id_ok = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+            (preceded_ok && id_ok).then(|| id.to_string())
+        })
+        .collect()
+}
+
+pub fn slug(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+pub fn strip_inline_code(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find('`') {
+        let ticks = rest[start..].len() - rest[start..].trim_start_matches('`').len();
+        let delim = &rest[start..start + ticks];
+        let after = &rest[start + ticks..];
+
+        let Some(end) = after.find(delim) else {
+            break;
+        };
+
+        out.push_str(&rest[..start]);
+        let span = ticks * 2 + end;
+        out.extend(std::iter::repeat_n(' ', span));
+        rest = &after[end + ticks..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_lines_skip_fences() {
+        let body = "a\n```yaml\nkey:: x\n```\nb\n~~~\nc\n~~~";
+        let lines: Vec<(usize, &str)> = body_lines(body).collect();
+        assert_eq!(lines, vec![(0, "a"), (4, "b")]);
+    }
+
+    #[test]
+    fn test_headings_and_sections() {
+        let body = "intro\n# Project\n## Decisions ##\nuse postgres\n```\n# not a heading\n```\n### Detail\nmore\n## Open Questions\nwhy?\n#tag\n    # indented code";
+        let texts: Vec<(usize, String)> = headings(body)
+            .into_iter()
+            .map(|h| (h.level, h.text))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (1, "Project".to_string()),
+                (2, "Decisions".to_string()),
+                (3, "Detail".to_string()),
+                (2, "Open Questions".to_string()),
+            ]
+        );
+
+        let sections = sections(body);
+        assert_eq!(
+            sections[1].1,
+            "use postgres\n```\n# not a heading\n```\n### Detail\nmore"
+        );
+        assert_eq!(sections[3].1, "why?\n#tag\n    # indented code");
+        assert_eq!(slug(&sections[3].0.text), "open-questions");
+    }
+
+    #[test]
+    fn test_block_ids() {
+        let body = "A paragraph ^para-1\n| table |\n^table\nnot^inline\nx ^bad_id";
+        assert_eq!(block_ids(body), vec!["para-1", "table"]);
+    }
+
+    #[test]
+    fn test_strip_inline_code() {
+        assert_eq!(
+            strip_inline_code("a `b::c` d"),
+            format!("a{}d", " ".repeat(8))
+        );
+        assert_eq!(strip_inline_code("``x ` y`` z"), " ".repeat(10) + "z");
+        assert_eq!(strip_inline_code("open `tick"), "open `tick");
+    }
+}
@@ -0,0 +1,144 @@
+use crate::source;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub struct Note {
+    pub path: PathBuf,
+    pub properties: YamlValue,
+    pub file: Mapping,
+    body: OnceLock<Option<Body>>,
+}
+
+pub struct Body {
+    pub text: String,
+    pub first_line: usize,
+}
+
+impl Note {
+    pub fn new(path: PathBuf, properties: YamlValue) -> Self {
+        Self {
+            path,
+            properties,
+            file: Mapping::new(),
+            body: OnceLock::new(),
+        }
+    }
+
+    pub fn with_body(self, body: Body) -> Self {
+        let _ = self.body.set(Some(body));
+        self
+    }
+
+    pub fn body(&self) -> Option<&Body> {
+        self.body
+            .get_or_init(|| {
+                let source = source::Source::of(&self.path, &[]);
+                let doc = source::parse_document(&self.path, source, Some(&[])).ok()?;
+                Some(Body {
+                    text: doc.body,
+                    first_line: doc.body_line,
+                })
+            })
+            .as_ref()
+    }
+
+    pub fn with_file_info(mut self, rel_path: &Path) -> Self {
+        let name = rel_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let folder = rel_path
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        self.set_file_field("name", name);
+        self.set_file_field("path", rel_path.to_string_lossy().to_string());
+        self.set_file_field("folder", folder);
+        self
+    }
+
+    pub fn rel_path(&self) -> &Path {
+        self.get("file.path")
+            .and_then(|p| p.as_str())
+            .map_or(&self.path, Path::new)
+    }
+
+    pub fn set_file_field(&mut self, key: &str, value: impl Into<YamlValue>) {
+        self.file.insert(YamlValue::from(key), value.into());
+    }
+
+    pub fn get(&self, field: &str) -> Option<&YamlValue> {
+        match split_file_field(field) {
+            Some(key) => get_path(&self.file, key),
+            None => get_path(self.properties.as_mapping()?, field),
+        }
+    }
+}
+
+pub fn is_body_field(field: &str) -> bool {
+    ["content", "body", "file.content"]
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(field))
+}
+
+pub fn split_file_field(field: &str) -> Option<&str> {
+    let (prefix, key) = field.split_once('.')?;
+    prefix.eq_ignore_ascii_case("file").then_some(key)
+}
+
+fn get_path<'a>(mapping: &'a Mapping, field: &str) -> Option<&'a YamlValue> {
+    if let Some(value) = get_case_insensitive(mapping, field) {
+        return Some(value);
+    }
+    let (head, rest) = field.split_once('.')?;
+    get_path(get_case_insensitive(mapping, head)?.as_mapping()?, rest)
+}
+
+fn get_case_insensitive<'a>(mapping: &'a Mapping, field: &str) -> Option<&'a YamlValue> {
+    let field_lower = field.to_lowercase();
+    for (key, value) in mapping {
+        if let Some(key_str) = key.as_str() {
+            if key_str.to_lowercase() == field_lower {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_file_and_property_fields() {
+        let fm: YamlValue = serde_yaml::from_str("Status: active\nfile: shadowed").unwrap();
+        let note = Note::new(PathBuf::from("/vault/Projects/A.md"), fm)
+            .with_file_info(Path::new("Projects/A.md"));
+
+        assert_eq!(note.get("status"), Some(&YamlValue::from("active")));
+        assert_eq!(note.get("file.name"), Some(&YamlValue::from("A")));
+        assert_eq!(note.get("File.Folder"), Some(&YamlValue::from("Projects")));
+        assert_eq!(note.get("file.tags"), None);
+    }
+
+    #[test]
+    fn test_get_nested_fields() {
+        let fm: YamlValue = serde_yaml::from_str("author:\n  Name: Ann\ndc.title: Dotted").unwrap();
+        let mut note = Note::new(PathBuf::from("a.md"), fm);
+        note.set_file_field(
+            "sections",
+            serde_yaml::from_str::<YamlValue>("decisions: use postgres").unwrap(),
+        );
+
+        assert_eq!(note.get("author.name"), Some(&YamlValue::from("Ann")));
+        assert_eq!(note.get("dc.title"), Some(&YamlValue::from("Dotted")));
+        assert_eq!(
+            note.get("file.sections.Decisions"),
+            Some(&YamlValue::from("use postgres"))
+        );
+        assert_eq!(note.get("author.name.first"), None);
+    }
+}
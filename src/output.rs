@@ -0,0 +1,133 @@
+use crate::vault::Vault;
+use crate::watch::Change;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    Relative,
+    Absolute,
+    Uri,
+}
+
+pub struct Format {
+    vault: PathBuf,
+    vault_name: String,
+    labeled: bool,
+    style: PathStyle,
+    terminator: u8,
+}
+
+impl Format {
+    pub fn new(vault: &Vault, style: PathStyle, null: bool) -> Format {
+        Format {
+            vault: std::path::absolute(&vault.path).unwrap_or_else(|_| vault.path.clone()),
+            vault_name: vault.name.clone(),
+            labeled: false,
+            style,
+            terminator: if null { b'\0' } else { b'\n' },
+        }
+    }
+
+    pub fn labeled(mut self, labeled: bool) -> Format {
+        self.labeled = labeled;
+        self
+    }
+
+    pub fn render(&self, path: &Path) -> String {
+        match self.style {
+            PathStyle::Relative if self.labeled => {
+                format!("{}:{}", self.vault_name, path.display())
+            }
+            PathStyle::Relative => path.display().to_string(),
+            PathStyle::Absolute => self.vault.join(path).display().to_string(),
+            PathStyle::Uri => {
+                let file = path.to_string_lossy().replace('\\', "/");
+                let file = file.strip_suffix(".md").unwrap_or(&file);
+                format!(
+                    "obsidian://open?vault={}&file={}",
+                    percent_encode(&self.vault_name),
+                    percent_encode(file)
+                )
+            }
+        }
+    }
+
+    pub fn print_path(&self, path: &Path) {
+        self.write_line(&self.render(path));
+    }
+
+    pub fn print_change(&self, change: &Change) {
+        match change {
+            Change::Added(path) => self.write_line(&format!("+ {}", self.render(path))),
+            Change::Removed(path) => self.write_line(&format!("- {}", self.render(path))),
+        }
+    }
+
+    pub fn print_location(&self, path: &Path, line: usize, text: &str) {
+        self.write_line(&format!("{}:{}: {}", self.render(path), line, text));
+    }
+
+    pub fn print_message(&self, path: &Path, text: &str) {
+        self.write_line(&format!("{}: {}", self.render(path), text));
+    }
+
+    fn write_line(&self, line: &str) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout
+            .write_all(line.as_bytes())
+            .and_then(|_| stdout.write_all(&[self.terminator]));
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+pub fn clear_screen() {
+    if io::stdout().is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_styles() {
+        let path = Path::new("Projects/Big Plan.md");
+        let vault = Vault::new(PathBuf::from("/vaults/Work Notes"));
+
+        let relative = Format::new(&vault, PathStyle::Relative, false);
+        assert_eq!(relative.render(path), "Projects/Big Plan.md");
+        assert_eq!(
+            relative.labeled(true).render(path),
+            "Work Notes:Projects/Big Plan.md"
+        );
+
+        let absolute = Format::new(&vault, PathStyle::Absolute, true).labeled(true);
+        assert_eq!(
+            absolute.render(path),
+            "/vaults/Work Notes/Projects/Big Plan.md"
+        );
+
+        let uri = Format::new(&vault, PathStyle::Uri, false);
+        assert_eq!(
+            uri.render(path),
+            "obsidian://open?vault=Work%20Notes&file=Projects%2FBig%20Plan"
+        );
+        assert_eq!(
+            uri.render(Path::new("img/ü.png")),
+            "obsidian://open?vault=Work%20Notes&file=img%2F%C3%BC.png"
+        );
+    }
+}
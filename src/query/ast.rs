@@ -9,10 +9,63 @@ pub enum Expr {
         field: String,
         value: Value,
     },
+    Matches {
+        field: String,
+        pattern: Pattern,
+    },
+    Truthy {
+        field: String,
+    },
+    Not(Box<Expr>),
     And(Box<Expr>, Box<Expr>),
     Or(Box<Expr>, Box<Expr>),
 }
 
+impl Expr {
+    pub fn fields(&self) -> Vec<&str> {
+        match self {
+            Expr::Compare { field, .. }
+            | Expr::Contains { field, .. }
+            | Expr::Matches { field, .. }
+            | Expr::Truthy { field } => vec![field.as_str()],
+            Expr::Not(inner) => inner.fields(),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                let mut fields = left.fields();
+                fields.extend(right.fields());
+                fields
+            }
+        }
+    }
+
+    pub fn required_fields(&self) -> Vec<&str> {
+        match self {
+            Expr::And(left, right) => {
+                let mut fields = left.required_fields();
+                fields.extend(right.required_fields());
+                fields
+            }
+            Expr::Or(left, right) => {
+                let right = right.required_fields();
+                left.required_fields()
+                    .into_iter()
+                    .filter(|f| right.contains(f))
+                    .collect()
+            }
+            Expr::Not(_) => Vec::new(),
+            _ => self.fields(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern(pub regex::Regex);
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompareOp {
     Eq,
@@ -1,49 +1,94 @@
-use super::ast::{CompareOp, Date, Expr, Value};
+use super::ast::{CompareOp, Date, Expr, Pattern, Value};
+use crate::link::{normalize_target, unquoted_wikilink, Link, LinkResolver};
+use crate::note::{is_body_field, split_file_field, Note};
+use crate::tags::tag_matches;
 use serde_yaml::Value as YamlValue;
 
-pub fn evaluate(expr: &Expr, frontmatter: &YamlValue) -> bool {
+#[derive(Default, Clone, Copy)]
+pub struct Context<'a> {
+    pub links: Option<&'a LinkResolver>,
+}
+
+pub fn evaluate(expr: &Expr, note: &Note, ctx: &Context) -> bool {
     match expr {
-        Expr::Compare { field, op, value } => eval_compare(frontmatter, field, *op, value),
-        Expr::Contains { field, value } => eval_contains(frontmatter, field, value),
-        Expr::And(left, right) => evaluate(left, frontmatter) && evaluate(right, frontmatter),
-        Expr::Or(left, right) => evaluate(left, frontmatter) || evaluate(right, frontmatter),
+        Expr::Compare { field, op, value } => eval_compare(note, field, *op, value, ctx),
+        Expr::Contains { field, value } => eval_contains(note, field, value, ctx),
+        Expr::Matches { field, pattern } => eval_matches(note, field, pattern),
+        Expr::Truthy { field } => note.get(field).is_some_and(is_truthy),
+        Expr::Not(inner) => !evaluate(inner, note, ctx),
+        Expr::And(left, right) => {
+            let (first, second) = cheap_first(left, right);
+            evaluate(first, note, ctx) && evaluate(second, note, ctx)
+        }
+        Expr::Or(left, right) => {
+            let (first, second) = cheap_first(left, right);
+            evaluate(first, note, ctx) || evaluate(second, note, ctx)
+        }
     }
 }
 
-fn get_field_case_insensitive<'a>(fm: &'a YamlValue, field: &str) -> Option<&'a YamlValue> {
-    let mapping = fm.as_mapping()?;
-    let field_lower = field.to_lowercase();
-    for (key, value) in mapping {
-        if let Some(key_str) = key.as_str() {
-            if key_str.to_lowercase() == field_lower {
-                return Some(value);
-            }
-        }
+// Body predicates read the note file, so frontmatter predicates get to short-circuit first.
+fn cheap_first<'a>(left: &'a Expr, right: &'a Expr) -> (&'a Expr, &'a Expr) {
+    if needs_body(left) && !needs_body(right) {
+        (right, left)
+    } else {
+        (left, right)
     }
-    None
 }
 
-fn strip_obsidian_link(s: &str) -> &str {
-    s.strip_prefix("[[")
-        .and_then(|s| s.strip_suffix("]]"))
-        .unwrap_or(s)
+fn needs_body(expr: &Expr) -> bool {
+    expr.fields().into_iter().any(is_body_field)
+}
+
+fn is_tag_field(field: &str) -> bool {
+    split_file_field(field)
+        .is_some_and(|key| key.eq_ignore_ascii_case("tags") || key.eq_ignore_ascii_case("etags"))
 }
 
 fn normalize_for_compare(s: &str) -> String {
-    strip_obsidian_link(s).to_lowercase()
+    match Link::parse(s) {
+        Some(link) => link.target.to_lowercase(),
+        None => s.to_lowercase(),
+    }
 }
 
-fn eval_compare(fm: &YamlValue, field: &str, op: CompareOp, value: &Value) -> bool {
-    try_eval_compare(fm, field, op, value).unwrap_or(false)
+fn link_key(s: &str, ctx: &Context) -> String {
+    let link = Link::parse(s);
+    let target = link.as_ref().map_or(s, |l| l.target.as_str());
+    match ctx.links.and_then(|r| r.resolve(target)) {
+        Some(path) => normalize_target(&path.to_string_lossy()),
+        None => normalize_target(target),
+    }
 }
 
-fn try_eval_compare(fm: &YamlValue, field: &str, op: CompareOp, value: &Value) -> Option<bool> {
-    let fm_value = get_field_case_insensitive(fm, field)?;
+fn values_equal(a: &str, b: &str, ctx: &Context) -> bool {
+    if Link::parse(a).is_none() && Link::parse(b).is_none() {
+        return a.to_lowercase() == b.to_lowercase();
+    }
+    link_key(a, ctx) == link_key(b, ctx)
+}
+
+fn eval_compare(note: &Note, field: &str, op: CompareOp, value: &Value, ctx: &Context) -> bool {
+    try_eval_compare(note, field, op, value, ctx).unwrap_or(false)
+}
+
+fn try_eval_compare(
+    note: &Note,
+    field: &str,
+    op: CompareOp,
+    value: &Value,
+    ctx: &Context,
+) -> Option<bool> {
+    let fm_value = note.get(field)?;
 
     match value {
         Value::String(s) => {
             let fm_str = yaml_to_string(fm_value)?;
-            compare_str(&fm_str, s, op)
+            match op {
+                CompareOp::Eq => Some(values_equal(&fm_str, s, ctx)),
+                CompareOp::Ne => Some(!values_equal(&fm_str, s, ctx)),
+                _ => compare_str(&fm_str, s, op),
+            }
         }
         Value::Number(n) => {
             let fm_num = yaml_to_number(fm_value)?;
@@ -64,37 +109,86 @@ fn try_eval_compare(fm: &YamlValue, field: &str, op: CompareOp, value: &Value) -
     }
 }
 
-fn eval_contains(fm: &YamlValue, field: &str, value: &Value) -> bool {
-    let Some(fm_value) = get_field_case_insensitive(fm, field) else {
+fn eval_contains(note: &Note, field: &str, value: &Value, ctx: &Context) -> bool {
+    let Value::String(needle) = value else {
         return false;
     };
 
-    let Value::String(needle) = value else {
+    if is_body_field(field) {
+        return note
+            .body()
+            .is_some_and(|b| b.text.to_lowercase().contains(&needle.to_lowercase()));
+    }
+
+    let Some(fm_value) = note.get(field) else {
         return false;
     };
 
+    if is_tag_field(field) {
+        return fm_value.as_sequence().is_some_and(|tags| {
+            tags.iter()
+                .filter_map(|t| t.as_str())
+                .any(|t| tag_matches(t, needle))
+        });
+    }
+
     let needle_normalized = normalize_for_compare(needle);
 
-    if let Some(arr) = fm_value.as_sequence() {
+    if let Some(arr) = fm_value
+        .as_sequence()
+        .filter(|_| unquoted_wikilink(fm_value).is_none())
+    {
         return arr.iter().any(|item| {
             yaml_to_string(item)
-                .map(|s| normalize_for_compare(&s) == needle_normalized)
+                .map(|s| values_equal(&s, needle, ctx))
                 .unwrap_or(false)
         });
     }
 
     if let Some(s) = yaml_to_string(fm_value) {
-        return normalize_for_compare(&s).contains(&needle_normalized);
+        return normalize_for_compare(&s).contains(&needle_normalized)
+            || values_equal(&s, needle, ctx);
     }
 
     false
 }
 
+fn eval_matches(note: &Note, field: &str, pattern: &Pattern) -> bool {
+    if is_body_field(field) {
+        return note.body().is_some_and(|b| pattern.0.is_match(&b.text));
+    }
+
+    let Some(fm_value) = note.get(field) else {
+        return false;
+    };
+
+    match fm_value.as_sequence() {
+        Some(items) => items
+            .iter()
+            .filter_map(yaml_to_string)
+            .any(|s| pattern.0.is_match(&s)),
+        None => yaml_to_string(fm_value).is_some_and(|s| pattern.0.is_match(&s)),
+    }
+}
+
+fn is_truthy(v: &YamlValue) -> bool {
+    match v {
+        YamlValue::Null => false,
+        YamlValue::Bool(b) => *b,
+        YamlValue::Number(n) => n.as_f64() != Some(0.0),
+        YamlValue::String(s) => !s.is_empty(),
+        YamlValue::Sequence(seq) => !seq.is_empty(),
+        YamlValue::Mapping(map) => !map.is_empty(),
+        YamlValue::Tagged(tagged) => is_truthy(&tagged.value),
+    }
+}
+
 fn yaml_to_string(v: &YamlValue) -> Option<String> {
     match v {
         YamlValue::String(s) => Some(s.clone()),
         YamlValue::Number(n) => Some(n.to_string()),
         YamlValue::Bool(b) => Some(b.to_string()),
+        YamlValue::Sequence(_) => unquoted_wikilink(v),
         _ => None,
     }
 }
@@ -147,68 +241,194 @@ fn compare_float(a: f64, b: f64, op: CompareOp) -> Option<bool> {
 mod tests {
     use super::*;
     use serde_yaml::from_str;
+    use std::path::PathBuf;
+
+    fn note(yaml: &str) -> Note {
+        Note::new(PathBuf::from("note.md"), from_str(yaml).unwrap())
+    }
 
     #[test]
     fn test_string_eq() {
-        let fm: YamlValue = from_str("status: active").unwrap();
+        let fm = note("status: active");
         let expr = Expr::Compare {
             field: "status".to_string(),
             op: CompareOp::Eq,
             value: Value::String("active".to_string()),
         };
-        assert!(evaluate(&expr, &fm));
+        assert!(evaluate(&expr, &fm, &Context::default()));
     }
 
     #[test]
     fn test_case_insensitive_field() {
-        let fm: YamlValue = from_str("Status: active").unwrap();
+        let fm = note("Status: active");
         let expr = Expr::Compare {
             field: "status".to_string(),
             op: CompareOp::Eq,
             value: Value::String("active".to_string()),
         };
-        assert!(evaluate(&expr, &fm));
+        assert!(evaluate(&expr, &fm, &Context::default()));
     }
 
     #[test]
     fn test_case_insensitive_value() {
-        let fm: YamlValue = from_str("status: ACTIVE").unwrap();
+        let fm = note("status: ACTIVE");
         let expr = Expr::Compare {
             field: "status".to_string(),
             op: CompareOp::Eq,
             value: Value::String("active".to_string()),
         };
-        assert!(evaluate(&expr, &fm));
+        assert!(evaluate(&expr, &fm, &Context::default()));
     }
 
     #[test]
     fn test_obsidian_link_stripping() {
-        let fm: YamlValue = from_str("project: \"[[Graph0mane]]\"").unwrap();
+        let fm = note("project: \"[[Graph0mane]]\"");
         let expr = Expr::Compare {
             field: "project".to_string(),
             op: CompareOp::Eq,
             value: Value::String("Graph0mane".to_string()),
         };
-        assert!(evaluate(&expr, &fm));
+        assert!(evaluate(&expr, &fm, &Context::default()));
+    }
+
+    #[test]
+    fn test_link_resolution() {
+        let paths = [
+            std::path::Path::new("Projects/Graph0mane.md"),
+            std::path::Path::new("Archive/2023/Graph0mane.md"),
+            std::path::Path::new("Archive/Other.md"),
+        ];
+        let resolver = LinkResolver::new(paths);
+        let ctx = Context {
+            links: Some(&resolver),
+        };
+
+        let fm = note("project: \"[[Projects/Graph0mane|Graph]]\"\nrelated: [\"[[Other#Notes]]\", \"[Graph](Graph0mane.md)\"]");
+
+        let eq = |field: &str, value: &str| Expr::Compare {
+            field: field.to_string(),
+            op: CompareOp::Eq,
+            value: Value::String(value.to_string()),
+        };
+        assert!(evaluate(&eq("project", "Graph0mane"), &fm, &ctx));
+        assert!(evaluate(&eq("project", "[[graph0mane.md]]"), &fm, &ctx));
+        assert!(!evaluate(&eq("project", "Archive/Graph0mane"), &fm, &ctx));
+
+        let contains = |value: &str| Expr::Contains {
+            field: "related".to_string(),
+            value: Value::String(value.to_string()),
+        };
+        assert!(evaluate(&contains("Other"), &fm, &ctx));
+        assert!(evaluate(&contains("[[Projects/Graph0mane]]"), &fm, &ctx));
+        assert!(!evaluate(&contains("Archive/Graph0mane"), &fm, &ctx));
+    }
+
+    #[test]
+    fn test_alias_resolution() {
+        let mut resolver = LinkResolver::new([std::path::Path::new("Projects/Graph0mane.md")]);
+        resolver.add_alias(std::path::Path::new("Projects/Graph0mane.md"), "GM");
+        let ctx = Context {
+            links: Some(&resolver),
+        };
+
+        let fm = note("project: \"[[GM]]\"");
+        let expr = Expr::Compare {
+            field: "project".to_string(),
+            op: CompareOp::Eq,
+            value: Value::String("Graph0mane".to_string()),
+        };
+        assert!(evaluate(&expr, &fm, &ctx));
+        assert!(!evaluate(&expr, &fm, &Context::default()));
+    }
+
+    #[test]
+    fn test_unquoted_wikilink() {
+        let fm = note("project: [[Graph0mane]]");
+        let expr = Expr::Compare {
+            field: "project".to_string(),
+            op: CompareOp::Eq,
+            value: Value::String("Graph0mane".to_string()),
+        };
+        assert!(evaluate(&expr, &fm, &Context::default()));
+    }
+
+    #[test]
+    fn test_tag_hierarchy() {
+        let mut fm = note("tags: [project]");
+        fm.set_file_field(
+            "etags",
+            from_str::<YamlValue>("['#area/health', '#project']").unwrap(),
+        );
+
+        let contains = |value: &str| Expr::Contains {
+            field: "file.etags".to_string(),
+            value: Value::String(value.to_string()),
+        };
+        assert!(evaluate(&contains("#area"), &fm, &Context::default()));
+        assert!(evaluate(&contains("area/health"), &fm, &Context::default()));
+        assert!(evaluate(&contains("Project"), &fm, &Context::default()));
+        assert!(!evaluate(&contains("health"), &fm, &Context::default()));
+    }
+
+    #[test]
+    fn test_body_predicates() {
+        let fm = note("status: active").with_body(crate::note::Body {
+            text: "Deploying to Kubernetes next week".to_string(),
+            first_line: 3,
+        });
+        let ctx = Context::default();
+
+        let expr =
+            crate::query::parse(r#"status = "active" AND content contains "kubernetes""#).unwrap();
+        assert!(evaluate(&expr, &fm, &ctx));
+
+        let expr = crate::query::parse("body matches /next\\s+week$/").unwrap();
+        assert!(evaluate(&expr, &fm, &ctx));
+
+        let expr = crate::query::parse("body matches /^kubernetes/").unwrap();
+        assert!(!evaluate(&expr, &fm, &ctx));
+    }
+
+    #[test]
+    fn test_truthy_and_not() {
+        let truthy = |field: &str| Expr::Truthy {
+            field: field.to_string(),
+        };
+        let not = |expr: Expr| Expr::Not(Box::new(expr));
+        let ctx = Context::default();
+
+        let fm = note(
+            "archived: false
+draft: true
+tags: []
+title: Plan",
+        );
+        assert!(evaluate(&truthy("draft"), &fm, &ctx));
+        assert!(evaluate(&truthy("title"), &fm, &ctx));
+        assert!(!evaluate(&truthy("archived"), &fm, &ctx));
+        assert!(!evaluate(&truthy("tags"), &fm, &ctx));
+        assert!(evaluate(&not(truthy("archived")), &fm, &ctx));
+        assert!(evaluate(&not(truthy("missing")), &fm, &ctx));
+        assert!(!evaluate(&not(truthy("draft")), &fm, &ctx));
     }
 
     #[test]
     fn test_contains_array() {
-        let fm: YamlValue = from_str("tags: [a, b, c]").unwrap();
+        let fm = note("tags: [a, b, c]");
         let expr = Expr::Contains {
             field: "tags".to_string(),
             value: Value::String("b".to_string()),
         };
-        assert!(evaluate(&expr, &fm));
+        assert!(evaluate(&expr, &fm, &Context::default()));
     }
 
     #[test]
     fn test_contains_case_insensitive() {
-        let fm: YamlValue = from_str("tags: [Project, TODO]").unwrap();
+        let fm = note("tags: [Project, TODO]");
         let expr = Expr::Contains {
             field: "tags".to_string(),
             value: Value::String("project".to_string()),
         };
-        assert!(evaluate(&expr, &fm));
+        assert!(evaluate(&expr, &fm, &Context::default()));
     }
 }
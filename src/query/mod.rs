@@ -2,5 +2,5 @@ pub mod ast;
 pub mod eval;
 pub mod parser;
 
-pub use eval::evaluate;
+pub use eval::{evaluate, Context};
 pub use parser::parse;
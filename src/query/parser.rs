@@ -1,4 +1,4 @@
-use super::ast::{CompareOp, Date, Expr, Value};
+use super::ast::{CompareOp, Date, Expr, Pattern, Value};
 
 pub struct Parser<'a> {
     input: &'a str,
@@ -71,15 +71,30 @@ impl<'a> Parser<'a> {
             return Ok(expr);
         }
 
+        if self.match_char('!') {
+            let expr = self.parse_primary()?;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+
         let field = self.parse_identifier()?;
         self.skip_whitespace();
 
+        if self.at_expression_end() {
+            return Ok(Expr::Truthy { field });
+        }
+
         if self.match_keyword("contains") {
             self.skip_whitespace();
             let value = self.parse_value()?;
             return Ok(Expr::Contains { field, value });
         }
 
+        if self.match_keyword("matches") {
+            self.skip_whitespace();
+            let pattern = self.parse_pattern()?;
+            return Ok(Expr::Matches { field, pattern });
+        }
+
         let op = self.parse_operator()?;
         self.skip_whitespace();
         let value = self.parse_value()?;
@@ -93,7 +108,7 @@ impl<'a> Parser<'a> {
 
         while self.pos < self.input.len() {
             let c = self.current_char();
-            if c.is_alphanumeric() || c == '_' || c == '-' {
+            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
                 self.pos += 1;
             } else {
                 break;
@@ -150,6 +165,10 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_string(&mut self) -> Result<Value, ParseError> {
+        self.read_string().map(Value::String)
+    }
+
+    fn read_string(&mut self) -> Result<String, ParseError> {
         let start = self.pos;
         while self.pos < self.input.len() && self.current_char() != '"' {
             self.pos += 1;
@@ -158,7 +177,52 @@ impl<'a> Parser<'a> {
         if !self.match_char('"') {
             return Err(self.error("Unterminated string"));
         }
-        Ok(Value::String(s))
+        Ok(s)
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let start = self.pos;
+        let source = if self.match_char('/') {
+            let mut source = String::new();
+            loop {
+                if self.pos >= self.input.len() {
+                    return Err(self.error("Unterminated regex"));
+                }
+                let c = self.current_char();
+                self.pos += c.len_utf8();
+                match c {
+                    '/' => break,
+                    '\\' if self.current_char() == '/' => {
+                        source.push('/');
+                        self.pos += 1;
+                    }
+                    _ => source.push(c),
+                }
+            }
+            source
+        } else if self.match_char('"') {
+            self.read_string()?
+        } else {
+            return Err(self.error("Expected /regex/ or string after 'matches'"));
+        };
+
+        let flags_start = self.pos;
+        while self.pos < self.input.len() && matches!(self.current_char(), 'i' | 'm' | 's' | 'x') {
+            self.pos += 1;
+        }
+        let flags = &self.input[flags_start..self.pos];
+        let source = if flags.is_empty() {
+            source
+        } else {
+            format!("(?{}){}", flags, source)
+        };
+
+        regex::Regex::new(&source)
+            .map(Pattern)
+            .map_err(|e| ParseError {
+                message: format!("Invalid regex: {}", e),
+                pos: start,
+            })
     }
 
     fn parse_number_or_date(&mut self) -> Result<Value, ParseError> {
@@ -190,6 +254,16 @@ impl<'a> Parser<'a> {
             .map_err(|_| self.error("Invalid number"))
     }
 
+    fn at_expression_end(&mut self) -> bool {
+        let start = self.pos;
+        let end = self.pos >= self.input.len()
+            || self.current_char() == ')'
+            || self.match_keyword("AND")
+            || self.match_keyword("OR");
+        self.pos = start;
+        end
+    }
+
     fn skip_whitespace(&mut self) {
         while self.pos < self.input.len() && self.current_char().is_whitespace() {
             self.pos += 1;
@@ -227,7 +301,7 @@ impl<'a> Parser<'a> {
             return false;
         }
         let after = remaining.chars().nth(kw.len());
-        if after.map_or(true, |c| !c.is_alphanumeric() && c != '_') {
+        if after.is_none_or(|c| !c.is_alphanumeric() && c != '_') {
             self.pos += kw.len();
             true
         } else {
@@ -286,6 +360,60 @@ mod tests {
         assert!(matches!(expr, Expr::Contains { .. }));
     }
 
+    #[test]
+    fn test_dotted_field() {
+        let expr = parse(r##"file.tags contains "#area""##).unwrap();
+        assert!(matches!(expr, Expr::Contains { ref field, .. } if field == "file.tags"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let expr = parse(r#"body matches /kube\/?rnetes/i AND status = "active""#).unwrap();
+        let Expr::And(left, _) = expr else {
+            panic!("Expected AND");
+        };
+        let Expr::Matches { field, pattern } = *left else {
+            panic!("Expected matches");
+        };
+        assert_eq!(field, "body");
+        assert!(pattern.0.is_match("KUBE/RNETES"));
+
+        assert!(parse("body matches /[/").is_err());
+        assert!(parse("body matches /open").is_err());
+    }
+
+    #[test]
+    fn test_not_and_truthy() {
+        let expr = parse(r#"status = "done" AND !archived"#).unwrap();
+        let Expr::And(_, right) = expr else {
+            panic!("Expected AND");
+        };
+        assert_eq!(
+            *right,
+            Expr::Not(Box::new(Expr::Truthy {
+                field: "archived".to_string()
+            }))
+        );
+
+        let expr = parse(r#"!(draft OR status = "done")"#).unwrap();
+        assert!(matches!(expr, Expr::Not(_)));
+        assert_eq!(expr.fields(), vec!["draft", "status"]);
+        assert!(expr.required_fields().is_empty());
+
+        assert!(parse("status !=").is_err());
+        assert!(parse("status active").is_err());
+    }
+
+    #[test]
+    fn test_required_fields() {
+        let expr = parse(
+            r#"type = "project" AND (status = "active" OR (status = "done" AND owner = "me"))"#,
+        )
+        .unwrap();
+        assert_eq!(expr.fields(), vec!["type", "status", "status", "owner"]);
+        assert_eq!(expr.required_fields(), vec!["type", "status"]);
+    }
+
     #[test]
     fn test_date() {
         let expr = parse("created >= 2024-01-01").unwrap();
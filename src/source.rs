@@ -0,0 +1,174 @@
+use crate::frontmatter::{self, Document, FrontmatterError};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Source {
+    #[value(skip)]
+    Markdown,
+    #[value(help = "Obsidian canvas files; text nodes become the body")]
+    Canvas,
+    #[value(help = "`<attachment>.yaml` files describing the attachment next to them")]
+    Sidecar,
+    #[value(skip)]
+    Yaml,
+}
+
+impl Source {
+    // YAML files are notes of their own when `--ext` lists them, and sidecars otherwise.
+    pub fn of(path: &Path, extensions: &[String]) -> Source {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("canvas") => Source::Canvas,
+            Some(ext @ ("yaml" | "yml"))
+                if extensions
+                    .iter()
+                    .any(|x| x.trim_start_matches('.').eq_ignore_ascii_case(ext)) =>
+            {
+                Source::Yaml
+            }
+            Some("yaml" | "yml") => Source::Sidecar,
+            _ => Source::Markdown,
+        }
+    }
+}
+
+pub fn is_sidecar(path: &Path) -> bool {
+    path.with_extension("").is_file()
+}
+
+pub fn note_path(rel_path: &Path, source: Source) -> PathBuf {
+    match source {
+        Source::Sidecar => rel_path.with_extension(""),
+        _ => rel_path.to_path_buf(),
+    }
+}
+
+pub fn parse_frontmatter(
+    path: &Path,
+    source: Source,
+    keys: Option<&[String]>,
+) -> Result<Option<YamlValue>, FrontmatterError> {
+    match source {
+        Source::Markdown => frontmatter::parse_frontmatter(path, keys),
+        _ => parse_document(path, source, keys).map(|doc| doc.frontmatter),
+    }
+}
+
+pub fn parse_document(
+    path: &Path,
+    source: Source,
+    keys: Option<&[String]>,
+) -> Result<Document, FrontmatterError> {
+    match source {
+        Source::Markdown => frontmatter::parse_document(path, keys),
+        Source::Canvas => parse_canvas(&frontmatter::read_file(path)?),
+        Source::Sidecar | Source::Yaml => Ok(Document {
+            frontmatter: Some(frontmatter::read_yaml(path, keys)?),
+            body: String::new(),
+            body_line: 0,
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct Canvas {
+    #[serde(default)]
+    nodes: Vec<CanvasNode>,
+    #[serde(default)]
+    edges: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct CanvasNode {
+    #[serde(rename = "type")]
+    kind: String,
+    text: Option<String>,
+    file: Option<String>,
+    url: Option<String>,
+    label: Option<String>,
+}
+
+fn parse_canvas(content: &str) -> Result<Document, FrontmatterError> {
+    let canvas: Canvas = serde_json::from_str(content).map_err(frontmatter::json_error)?;
+
+    let mut texts = Vec::new();
+    let mut files = Vec::new();
+    let mut urls = Vec::new();
+    let mut groups = Vec::new();
+    for node in &canvas.nodes {
+        match (node.kind.as_str(), node) {
+            ("text", CanvasNode { text: Some(t), .. }) => texts.push(t.as_str()),
+            ("file", CanvasNode { file: Some(f), .. }) => {
+                files.push(YamlValue::from(format!("[[{}]]", f)))
+            }
+            ("link", CanvasNode { url: Some(u), .. }) => urls.push(YamlValue::from(u.as_str())),
+            ("group", CanvasNode { label: Some(l), .. }) => {
+                groups.push(YamlValue::from(l.as_str()))
+            }
+            _ => {}
+        }
+    }
+
+    let mut properties = Mapping::new();
+    properties.insert("nodes".into(), (canvas.nodes.len() as u64).into());
+    properties.insert("edges".into(), (canvas.edges.len() as u64).into());
+    properties.insert("files".into(), files.into());
+    properties.insert("urls".into(), urls.into());
+    properties.insert("groups".into(), groups.into());
+
+    Ok(Document {
+        frontmatter: Some(YamlValue::Mapping(properties)),
+        body: texts.join("\n\n"),
+        body_line: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_canvas() {
+        let content = r##"{
+            "nodes": [
+                {"id": "1", "type": "text", "text": "# Plan\n- [ ] ship #launch", "x": 0, "y": 0},
+                {"id": "2", "type": "file", "file": "Projects/Graph.md"},
+                {"id": "3", "type": "link", "url": "https://example.com"},
+                {"id": "4", "type": "group", "label": "Later"},
+                {"id": "5", "type": "text", "text": "status:: draft"}
+            ],
+            "edges": [{"id": "e1", "fromNode": "1", "toNode": "2"}]
+        }"##;
+        let doc = parse_canvas(content).unwrap();
+        let fm = doc.frontmatter.unwrap();
+
+        assert_eq!(fm["nodes"], 5);
+        assert_eq!(fm["edges"], 1);
+        assert_eq!(fm["files"], YamlValue::from(vec!["[[Projects/Graph.md]]"]));
+        assert_eq!(fm["groups"], YamlValue::from(vec!["Later"]));
+        assert_eq!(doc.body, "# Plan\n- [ ] ship #launch\n\nstatus:: draft");
+
+        let Err(err) = parse_canvas("{\"nodes\": [}") else {
+            panic!("Expected JSON error");
+        };
+        assert_eq!(err.line(), Some(1));
+        assert!(err.to_string().starts_with("invalid JSON at line 1"));
+    }
+
+    #[test]
+    fn test_note_path() {
+        let note = |rel: &str, extensions: &[&str]| {
+            let extensions: Vec<String> = extensions.iter().map(|e| e.to_string()).collect();
+            note_path(Path::new(rel), Source::of(Path::new(rel), &extensions))
+        };
+        assert_eq!(note("a/img.png.yaml", &["md"]), Path::new("a/img.png"));
+        assert_eq!(
+            note("a/img.png.yaml", &["md", ".yaml"]),
+            Path::new("a/img.png.yaml")
+        );
+        assert_eq!(note("a/Board.canvas", &["md"]), Path::new("a/Board.canvas"));
+        assert_eq!(note("a/Note.qmd", &["qmd"]), Path::new("a/Note.qmd"));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::markdown::{body_lines, strip_inline_code};
+use serde_yaml::Value as YamlValue;
+
+pub fn extract_body_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+
+    for (_, line) in body_lines(body) {
+        let line = strip_inline_code(line);
+        let mut prev: Option<char> = None;
+
+        for (idx, c) in line.char_indices() {
+            if c == '#' && prev.is_none_or(char::is_whitespace) {
+                let rest = &line[idx + 1..];
+                let len = rest.find(|ch: char| !is_tag_char(ch)).unwrap_or(rest.len());
+                if let Some(tag) = normalize_tag(&rest[..len]) {
+                    tags.push(tag);
+                }
+            }
+            prev = Some(c);
+        }
+    }
+
+    tags
+}
+
+pub fn frontmatter_tags(fm: &YamlValue) -> Vec<String> {
+    let Some(mapping) = fm.as_mapping() else {
+        return Vec::new();
+    };
+
+    let mut tags = Vec::new();
+    for (key, value) in mapping {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if !key.eq_ignore_ascii_case("tags") && !key.eq_ignore_ascii_case("tag") {
+            continue;
+        }
+        match value {
+            YamlValue::String(s) => tags.extend(
+                s.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter_map(normalize_tag),
+            ),
+            YamlValue::Sequence(items) => tags.extend(
+                items
+                    .iter()
+                    .filter_map(|i| i.as_str())
+                    .filter_map(normalize_tag),
+            ),
+            _ => {}
+        }
+    }
+
+    tags
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_matches('/');
+    if tag.is_empty() || tag.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("#{}", tag))
+}
+
+pub fn expand_parents(tags: &[String]) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for tag in tags {
+        for (idx, _) in tag.match_indices('/') {
+            push_unique(&mut expanded, &tag[..idx]);
+        }
+        push_unique(&mut expanded, tag);
+    }
+    expanded
+}
+
+pub fn dedupe(tags: Vec<String>) -> Vec<String> {
+    let mut unique = Vec::new();
+    for tag in &tags {
+        push_unique(&mut unique, tag);
+    }
+    unique
+}
+
+fn push_unique(tags: &mut Vec<String>, tag: &str) {
+    if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+        tags.push(tag.to_string());
+    }
+}
+
+pub fn tag_matches(tag: &str, needle: &str) -> bool {
+    let tag = tag.trim_start_matches('#').to_lowercase();
+    let needle = needle.trim().trim_start_matches('#').to_lowercase();
+    tag == needle || tag.starts_with(&format!("{}/", needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_body_tags() {
+        let body = "# Heading\nNotes #meeting and #area/health/\n\
+                    See https://example.com/page#anchor and [[Note#Section]]\n\
+                    `#code` #2024 #ok-1\n```\n#fenced\n```";
+        assert_eq!(
+            extract_body_tags(body),
+            vec!["#meeting", "#area/health", "#ok-1"]
+        );
+    }
+
+    #[test]
+    fn test_frontmatter_tags() {
+        let fm: YamlValue = serde_yaml::from_str("tags: [project, \"#area/work\"]").unwrap();
+        assert_eq!(frontmatter_tags(&fm), vec!["#project", "#area/work"]);
+
+        let fm: YamlValue = serde_yaml::from_str("Tags: one, two").unwrap();
+        assert_eq!(frontmatter_tags(&fm), vec!["#one", "#two"]);
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let tags = vec!["#area/health/sleep".to_string(), "#Area".to_string()];
+        assert_eq!(
+            expand_parents(&tags),
+            vec!["#area", "#area/health", "#area/health/sleep"]
+        );
+
+        assert!(tag_matches("#area/health", "#area"));
+        assert!(tag_matches("#area/health", "Area/Health"));
+        assert!(!tag_matches("#areas", "area"));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::inline::extract_inline_fields;
+use crate::markdown::body_lines;
+use serde_yaml::{Mapping, Value as YamlValue};
+
+const EMOJI_DATES: [(char, &str); 5] = [
+    ('📅', "due"),
+    ('⏳', "scheduled"),
+    ('✅', "completion"),
+    ('🛫', "start"),
+    ('➕', "created"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub line: usize,
+    pub indent: usize,
+    pub status: char,
+    pub text: String,
+    pub parent: Option<usize>,
+    pub fields: Vec<(String, YamlValue)>,
+}
+
+impl Task {
+    pub fn completed(&self) -> bool {
+        matches!(self.status, 'x' | 'X')
+    }
+
+    pub fn to_properties(&self, note: &YamlValue) -> YamlValue {
+        let mut mapping = Mapping::new();
+        mapping.insert("status".into(), self.status.to_string().into());
+        mapping.insert("completed".into(), self.completed().into());
+        mapping.insert("checked".into(), (self.status != ' ').into());
+        mapping.insert("text".into(), self.text.clone().into());
+        mapping.insert("line".into(), (self.line as u64).into());
+        if let Some(parent) = self.parent {
+            mapping.insert("parent".into(), (parent as u64).into());
+        }
+        for (key, value) in &self.fields {
+            mapping
+                .entry(key.as_str().into())
+                .or_insert_with(|| value.clone());
+        }
+        for (key, value) in note.as_mapping().into_iter().flatten() {
+            mapping.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        YamlValue::Mapping(mapping)
+    }
+}
+
+pub fn extract_tasks(body: &str, first_line: usize) -> Vec<Task> {
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+
+    for (idx, line) in body_lines(body) {
+        let Some((indent, status, text)) = parse_task_line(line) else {
+            continue;
+        };
+
+        while stack.last().is_some_and(|&(i, _)| i >= indent) {
+            stack.pop();
+        }
+
+        let line_no = first_line + idx + 1;
+        let mut fields = emoji_dates(text);
+        fields.extend(extract_inline_fields(text));
+
+        tasks.push(Task {
+            line: line_no,
+            indent,
+            status,
+            text: text.to_string(),
+            parent: stack.last().map(|&(_, l)| l),
+            fields,
+        });
+        stack.push((indent, line_no));
+    }
+
+    tasks
+}
+
+fn parse_task_line(line: &str) -> Option<(usize, char, &str)> {
+    let content = line.trim_start();
+    let indent: usize = line[..line.len() - content.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+
+    let rest = match content.split_once(' ') {
+        Some((marker, rest)) if is_list_marker(marker) => rest.trim_start(),
+        _ => return None,
+    };
+
+    let rest = rest.strip_prefix('[')?;
+    let mut chars = rest.chars();
+    let status = chars.next()?;
+    let rest = chars.as_str().strip_prefix(']')?;
+
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    Some((indent, status, rest.trim()))
+}
+
+fn is_list_marker(marker: &str) -> bool {
+    if matches!(marker, "-" | "*" | "+") {
+        return true;
+    }
+    let digits = marker.trim_end_matches(['.', ')']);
+    digits.len() + 1 == marker.len()
+        && !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn emoji_dates(text: &str) -> Vec<(String, YamlValue)> {
+    let mut fields = Vec::new();
+
+    for (emoji, key) in EMOJI_DATES {
+        let Some(idx) = text.find(emoji) else {
+            continue;
+        };
+        let after = text[idx + emoji.len_utf8()..].trim_start_matches([' ', '\u{fe0f}']);
+        let date = after.get(..10).filter(|d| is_iso_date(d));
+        if let Some(date) = date {
+            fields.push((key.to_string(), YamlValue::from(date)));
+        }
+    }
+
+    fields
+}
+
+fn is_iso_date(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 10
+        && b[4] == b'-'
+        && b[7] == b'-'
+        && b.iter()
+            .enumerate()
+            .all(|(i, c)| i == 4 || i == 7 || c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_tasks() {
+        let body = "# Todo\n- [ ] write spec 📅 2024-05-01\n  - [x] outline ✅ 2024-04-20\n  - [/] draft [owner:: Alice]\n* [-] dropped\nnot - [ ] a task\n```\n- [ ] fenced\n```\n1. [ ] numbered ⏳2024-06-01";
+        let tasks = extract_tasks(body, 3);
+
+        let summary: Vec<(usize, char, Option<usize>)> =
+            tasks.iter().map(|t| (t.line, t.status, t.parent)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (5, ' ', None),
+                (6, 'x', Some(5)),
+                (7, '/', Some(5)),
+                (8, '-', None),
+                (13, ' ', None),
+            ]
+        );
+
+        assert_eq!(tasks[0].text, "write spec 📅 2024-05-01");
+        assert_eq!(
+            tasks[0].fields,
+            vec![("due".to_string(), YamlValue::from("2024-05-01"))]
+        );
+        assert!(tasks[1].completed());
+        assert_eq!(
+            tasks[2].fields,
+            vec![("owner".to_string(), YamlValue::from("Alice"))]
+        );
+        assert_eq!(
+            tasks[4].fields,
+            vec![("scheduled".to_string(), YamlValue::from("2024-06-01"))]
+        );
+    }
+
+    #[test]
+    fn test_task_properties() {
+        let tasks = extract_tasks("- [x] done ✅ 2024-04-20", 0);
+        let note: YamlValue = serde_yaml::from_str("status: active\nproject: ovq").unwrap();
+        let props = tasks[0].to_properties(&note);
+        assert_eq!(props["completed"], YamlValue::from(true));
+        assert_eq!(props["status"], YamlValue::from("x"));
+        assert_eq!(props["completion"], YamlValue::from("2024-04-20"));
+        assert_eq!(props["line"], YamlValue::from(1));
+        assert_eq!(props["project"], YamlValue::from("ovq"));
+    }
+}
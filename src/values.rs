@@ -1,10 +1,13 @@
 use serde_yaml::Value as YamlValue;
 use std::collections::HashMap;
 
-pub fn collect_values(frontmatters: &[(String, YamlValue)], property: &str) -> HashMap<String, usize> {
+pub fn collect_values<'a>(
+    frontmatters: impl IntoIterator<Item = &'a YamlValue>,
+    property: &str,
+) -> HashMap<String, usize> {
     let mut counts: HashMap<String, usize> = HashMap::new();
 
-    for (_, fm) in frontmatters {
+    for fm in frontmatters {
         let Some(value) = fm.get(property) else {
             continue;
         };
@@ -63,11 +66,7 @@ mod tests {
         let fm2: YamlValue = from_str("status: done").unwrap();
         let fm3: YamlValue = from_str("status: active").unwrap();
 
-        let data = vec![
-            ("a.md".to_string(), fm1),
-            ("b.md".to_string(), fm2),
-            ("c.md".to_string(), fm3),
-        ];
+        let data = vec![fm1, fm2, fm3];
 
         let counts = collect_values(&data, "status");
         assert_eq!(counts.get("active"), Some(&2));
@@ -77,7 +76,7 @@ mod tests {
     #[test]
     fn test_collect_array_values() {
         let fm: YamlValue = from_str("tags: [a, b, a]").unwrap();
-        let data = vec![("x.md".to_string(), fm)];
+        let data = vec![fm];
 
         let counts = collect_values(&data, "tags");
         assert_eq!(counts.get("a"), Some(&2));
@@ -1,35 +1,563 @@
-use ignore::WalkBuilder;
-use std::io::{self, BufRead};
+use crate::source::{self, Source};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
 
-pub fn collect_markdown_files(vault_path: &Path) -> Vec<PathBuf> {
-    let mut files = Vec::new();
+pub struct Vault {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Vault {
+    pub fn new(path: PathBuf) -> Vault {
+        let name = fs::canonicalize(&path)
+            .unwrap_or_else(|_| path.clone())
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Vault { name, path }
+    }
+}
+
+pub fn open_vaults(paths: Vec<PathBuf>) -> Result<Vec<Vault>, String> {
+    let mut vaults: Vec<Vault> = Vec::new();
+    for path in paths {
+        let vault = Vault::new(path);
+        if let Some(other) = vaults.iter().find(|v| v.name == vault.name) {
+            return Err(format!(
+                "Vaults {} and {} are both named '{}'",
+                other.path.display(),
+                vault.path.display(),
+                vault.name
+            ));
+        }
+        vaults.push(vault);
+    }
+    Ok(vaults)
+}
+
+pub struct Filter {
+    extensions: Vec<String>,
+    sources: Vec<Source>,
+    excluded_prefixes: Vec<String>,
+    excluded_patterns: Vec<Regex>,
+    overrides: Option<Override>,
+    follow_symlinks: bool,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter {
+            extensions: vec!["md".to_string()],
+            sources: Vec::new(),
+            excluded_prefixes: Vec::new(),
+            excluded_patterns: Vec::new(),
+            overrides: None,
+            follow_symlinks: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AppConfig {
+    #[serde(default)]
+    user_ignore_filters: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct TemplatesConfig {
+    #[serde(default, alias = "templates_folder")]
+    folder: Option<String>,
+}
+
+impl Filter {
+    // Obsidian still resolves links to notes its settings hide, so only its own folders are left out.
+    pub fn linkable() -> Filter {
+        Filter {
+            excluded_prefixes: vec![".obsidian/".to_string(), ".trash/".to_string()],
+            ..Filter::default()
+        }
+    }
+
+    pub fn obsidian(vault_path: &Path) -> Filter {
+        let config_dir = vault_path.join(".obsidian");
+        let mut filter = Filter::linkable();
+
+        let app: AppConfig = read_json(&config_dir.join("app.json"));
+        for entry in app.user_ignore_filters {
+            let pattern = entry
+                .strip_prefix('/')
+                .and_then(|p| p.strip_suffix('/'))
+                .filter(|p| !p.is_empty());
+            match pattern {
+                Some(pattern) => filter.excluded_patterns.extend(Regex::new(pattern).ok()),
+                None => filter.excluded_prefixes.push(entry),
+            }
+        }
+
+        for config in ["templates.json", "plugins/templater-obsidian/data.json"] {
+            let templates: TemplatesConfig = read_json(&config_dir.join(config));
+            let folder = templates.folder.unwrap_or_default();
+            let folder = folder.trim_matches('/');
+            if !folder.is_empty() {
+                filter.excluded_prefixes.push(format!("{}/", folder));
+            }
+        }
+
+        filter
+    }
+
+    pub fn with_sources(mut self, extensions: &[String], sources: &[Source]) -> Filter {
+        self.extensions = extensions
+            .iter()
+            .map(|e| e.trim_start_matches('.').to_string())
+            .collect();
+        self.sources = sources.to_vec();
+        self
+    }
 
-    let walker = WalkBuilder::new(vault_path)
+    pub fn is_note(&self, path: &Path) -> bool {
+        match Source::of(path, &self.extensions) {
+            Source::Canvas => self.sources.contains(&Source::Canvas),
+            Source::Sidecar => self.sources.contains(&Source::Sidecar) && source::is_sidecar(path),
+            Source::Markdown | Source::Yaml => path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| self.extensions.iter().any(|x| x.eq_ignore_ascii_case(e))),
+        }
+    }
+
+    pub fn with_symlinks(mut self, follow: bool) -> Filter {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    pub fn set_globs(
+        &mut self,
+        vault_path: &Path,
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<(), ignore::Error> {
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = OverrideBuilder::new(vault_path);
+        for glob in include {
+            builder.add(glob)?;
+        }
+        for glob in exclude {
+            builder.add(&format!("!{}", glob))?;
+        }
+        self.overrides = Some(builder.build()?);
+        Ok(())
+    }
+
+    pub fn is_excluded(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let mut path = rel_path.to_string_lossy().replace('\\', "/");
+        if path.is_empty() {
+            return false;
+        }
+        if is_dir {
+            path.push('/');
+        }
+        self.excluded_prefixes
+            .iter()
+            .any(|p| path.starts_with(p.as_str()))
+            || self.excluded_patterns.iter().any(|r| r.is_match(&path))
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn collect_notes(vault_path: &Path, filter: &Filter) -> Vec<PathBuf> {
+    collect_files(vault_path, filter, |path| filter.is_note(path))
+}
+
+pub fn collect_attachments(vault_path: &Path, filter: &Filter) -> Vec<PathBuf> {
+    collect_files(vault_path, filter, |path| !filter.is_note(path))
+}
+
+fn collect_files(
+    vault_path: &Path,
+    filter: &Filter,
+    keep: impl Fn(&Path) -> bool + Sync,
+) -> Vec<PathBuf> {
+    let files = Mutex::new(Vec::new());
+
+    let mut builder = WalkBuilder::new(vault_path);
+    if let Some(overrides) = &filter.overrides {
+        builder.overrides(overrides.clone());
+    }
+
+    let walker = builder
         .hidden(false)
         .git_ignore(true)
         .git_global(false)
         .git_exclude(false)
         .add_custom_ignore_filename(".obsidianignore")
-        .build();
+        .follow_links(filter.follow_symlinks)
+        .threads(rayon::current_num_threads())
+        .build_parallel();
+
+    walker.run(|| {
+        Box::new(|entry| {
+            if let Err(e) = &entry {
+                if let Some(child) = symlink_loop(e) {
+                    let rel_path = child.strip_prefix(vault_path).unwrap_or(child);
+                    warn_once(format!("Skipping symlink loop at {}", rel_path.display()));
+                }
+            }
+            if let Ok(entry) = entry {
+                let path = entry.path();
+                let rel_path = path.strip_prefix(vault_path).unwrap_or(path);
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                if filter.is_excluded(rel_path, is_dir) {
+                    return WalkState::Skip;
+                }
+                if path.is_file() && keep(path) {
+                    files.lock().unwrap().push(path.to_path_buf());
+                }
+            }
+            WalkState::Continue
+        })
+    });
+
+    let mut files = files.into_inner().unwrap();
+    files.sort();
+    files.dedup();
+    if filter.follow_symlinks {
+        files = dedupe_canonical(files, vault_path);
+    }
+    files
+}
 
-    for entry in walker.flatten() {
-        let path = entry.path();
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-            files.push(path.to_path_buf());
+pub fn in_folders(rel_path: &Path, folders: &[String]) -> bool {
+    folders.is_empty()
+        || folders
+            .iter()
+            .any(|f| rel_path.starts_with(f.trim_matches('/')))
+}
+
+fn symlink_loop(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        _ => None,
+    }
+}
+
+// Walks repeat on every watch refresh and for attachments, so each warning is printed once.
+fn warn_once(message: String) {
+    static REPORTED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Mutex::default);
+    if REPORTED.lock().unwrap().insert(message.clone()) {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+fn dedupe_canonical(files: Vec<PathBuf>, vault_path: &Path) -> Vec<PathBuf> {
+    let vault = fs::canonicalize(vault_path).unwrap_or_else(|_| vault_path.to_path_buf());
+    let is_direct = |path: &Path, canonical: &Path| {
+        path.strip_prefix(vault_path)
+            .is_ok_and(|rel| vault.join(rel) == canonical)
+    };
+
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    let mut kept: Vec<PathBuf> = Vec::new();
+    for path in files {
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        let Some(&i) = seen.get(&canonical) else {
+            seen.insert(canonical, kept.len());
+            kept.push(path);
+            continue;
+        };
+
+        let skipped = if is_direct(&path, &canonical) && !is_direct(&kept[i], &canonical) {
+            std::mem::replace(&mut kept[i], path)
+        } else {
+            path
+        };
+        let rel = |p: &Path| {
+            p.strip_prefix(vault_path)
+                .unwrap_or(p)
+                .display()
+                .to_string()
+        };
+        warn_once(format!(
+            "{} and {} both resolve to {}; using {}",
+            rel(&skipped),
+            rel(&kept[i]),
+            canonical.display(),
+            rel(&kept[i])
+        ));
+    }
+    kept.sort();
+    kept
+}
+
+pub fn read_paths_from_stdin(vault_path: &Path, null: bool) -> Vec<PathBuf> {
+    let mut input = Vec::new();
+    if let Err(e) = io::stdin().lock().read_to_end(&mut input) {
+        eprintln!("Warning: Cannot read stdin: {}", e);
+    }
+    resolve_paths(&input, vault_path, null)
+}
+
+fn resolve_paths(input: &[u8], vault_path: &Path, null: bool) -> Vec<PathBuf> {
+    let separator = if null { b'\0' } else { b'\n' };
+    let mut seen = HashSet::new();
+    let mut paths = Vec::new();
+
+    for entry in input.split(|&b| b == separator) {
+        let entry = if null {
+            entry
+        } else {
+            entry.strip_suffix(b"\r").unwrap_or(entry)
+        };
+        if entry.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        let path = bytes_to_path(entry);
+        let found = if path.is_absolute() {
+            Some(path.clone()).filter(|p| p.is_file())
+        } else {
+            [vault_path.join(&path), path.clone()]
+                .into_iter()
+                .find(|p| p.is_file())
+        };
+
+        let Some(found) = found else {
+            eprintln!("Warning: No such file: {}", path.display());
+            continue;
+        };
+        let key = fs::canonicalize(&found).unwrap_or_else(|_| found.clone());
+        if seen.insert(key) {
+            paths.push(found);
         }
     }
 
-    files
+    paths
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
 }
 
-pub fn read_paths_from_stdin() -> Vec<PathBuf> {
-    let stdin = io::stdin();
-    stdin
-        .lock()
-        .lines()
-        .flatten()
-        .filter(|line| !line.trim().is_empty())
-        .map(PathBuf::from)
-        .collect()
+#[cfg(not(unix))]
+fn bytes_to_path(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn test_open_vaults() {
+        let vaults = open_vaults(vec![
+            PathBuf::from("/vaults/work"),
+            PathBuf::from("/vaults/Personal Notes"),
+        ])
+        .unwrap();
+        let names: Vec<&str> = vaults.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["work", "Personal Notes"]);
+
+        assert!(open_vaults(vec![PathBuf::from("/a/notes"), PathBuf::from("/b/notes"),]).is_err());
+    }
+
+    #[test]
+    fn test_collect_files_sorted() {
+        let vault = TempDir::new("vault");
+        for rel in ["b.md", "a/z.md", "a/c.md", "img.png", "Z.md"] {
+            vault.write(rel, "");
+        }
+
+        let files = vault.relative(collect_notes(vault.path(), &Filter::default()));
+        assert_eq!(
+            files,
+            ["Z.md", "a/c.md", "a/z.md", "b.md"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_extensions_and_sources() {
+        let vault = TempDir::new("sources");
+        for rel in [
+            "a.md",
+            "b.qmd",
+            "c.mdx",
+            "Board.canvas",
+            "img.png",
+            "img.png.yaml",
+            "config.yaml",
+        ] {
+            vault.write(rel, "");
+        }
+
+        let filter = Filter::default().with_sources(
+            &["md".to_string(), ".qmd".to_string()],
+            &[Source::Canvas, Source::Sidecar],
+        );
+        let notes = vault.relative(collect_notes(vault.path(), &filter));
+        let attachments = vault.relative(collect_attachments(vault.path(), &filter));
+        let markdown = vault.relative(collect_notes(vault.path(), &Filter::default()));
+        let yaml = Filter::default().with_sources(&["yaml".to_string()], &[Source::Sidecar]);
+        let yaml = vault.relative(collect_notes(vault.path(), &yaml));
+
+        assert_eq!(
+            notes,
+            ["Board.canvas", "a.md", "b.qmd", "img.png.yaml"].map(PathBuf::from)
+        );
+        assert_eq!(
+            attachments,
+            ["c.mdx", "config.yaml", "img.png"].map(PathBuf::from)
+        );
+        assert_eq!(markdown, [PathBuf::from("a.md")]);
+        assert_eq!(yaml, ["config.yaml", "img.png.yaml"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_resolve_stdin_paths() {
+        let vault = TempDir::new("stdin");
+        for rel in ["a.md", "Sub/b.md", "new\nline.md"] {
+            vault.write(rel, "");
+        }
+        let vault = vault.path();
+
+        let absolute = vault.join("a.md");
+        let lines = format!(
+            "a.md\r\nSub/b.md\n./a.md\n{}\n\nmissing.md\n",
+            absolute.display()
+        );
+        let by_line = resolve_paths(lines.as_bytes(), vault, false);
+
+        let mut nul = b"new\nline.md\0Sub/b.md\0".to_vec();
+        nul.extend_from_slice(absolute.to_string_lossy().as_bytes());
+        let by_nul = resolve_paths(&nul, vault, true);
+
+        assert_eq!(by_line, vec![vault.join("a.md"), vault.join("Sub/b.md")]);
+        assert_eq!(
+            by_nul,
+            vec![vault.join("new\nline.md"), vault.join("Sub/b.md"), absolute]
+        );
+    }
+
+    #[test]
+    fn test_obsidian_exclusions() {
+        let vault = TempDir::new("excluded");
+        let files = [
+            ("note.md", ""),
+            ("Archive/old.md", ""),
+            ("Archived.md", ""),
+            ("Daily/2024-01-01.md", ""),
+            ("Daily/notes.md", ""),
+            ("Tpl/daily.md", ""),
+            (".trash/deleted.md", ""),
+            (".obsidian/snippets/readme.md", ""),
+            (
+                ".obsidian/app.json",
+                r#"{"userIgnoreFilters": ["Archive/", "/\\d{4}-\\d{2}-\\d{2}/"]}"#,
+            ),
+            (".obsidian/templates.json", r#"{"folder": "/Tpl"}"#),
+        ];
+        for (rel, content) in files {
+            vault.write(rel, content);
+        }
+
+        let filtered = vault.relative(collect_notes(vault.path(), &Filter::obsidian(vault.path())));
+        let everything = vault.relative(collect_notes(vault.path(), &Filter::default()));
+
+        assert_eq!(
+            filtered,
+            ["Archived.md", "Daily/notes.md", "note.md"].map(PathBuf::from)
+        );
+        assert_eq!(everything.len(), 8);
+    }
+
+    #[test]
+    fn test_globs_and_folders() {
+        let vault = TempDir::new("globs");
+        for rel in [
+            "inbox.md",
+            "Projects/a.md",
+            "Projects/Archive/b.md",
+            "Areas/c.md",
+            "Archive/d.md",
+        ] {
+            vault.write(rel, "");
+        }
+
+        let collect = |filter: Filter| vault.relative(collect_notes(vault.path(), &filter));
+        let globs = |include: &[&str], exclude: &[&str]| {
+            let include: Vec<String> = include.iter().map(|g| g.to_string()).collect();
+            let exclude: Vec<String> = exclude.iter().map(|g| g.to_string()).collect();
+            let mut filter = Filter::default();
+            filter.set_globs(vault.path(), &include, &exclude).unwrap();
+            filter
+        };
+
+        let included = collect(globs(&["Projects/**"], &[]));
+        let excluded = collect(globs(&[], &["**/Archive/**"]));
+        let folders = ["Projects/".to_string(), "Areas".to_string()];
+        let mut scoped = collect(globs(&[], &["Archive/**"]));
+        scoped.retain(|p| in_folders(p, &folders));
+
+        assert_eq!(
+            included,
+            ["Projects/Archive/b.md", "Projects/a.md"].map(PathBuf::from)
+        );
+        assert_eq!(
+            excluded,
+            ["Areas/c.md", "Projects/a.md", "inbox.md"].map(PathBuf::from)
+        );
+        assert_eq!(
+            scoped,
+            ["Areas/c.md", "Projects/Archive/b.md", "Projects/a.md"].map(PathBuf::from)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let root = TempDir::new("symlinks");
+        root.write("vault/Projects/a.md", "");
+        root.write("shared/s.md", "");
+        let vault = root.path().join("vault");
+        symlink(root.path().join("shared"), vault.join("Shared")).unwrap();
+        symlink(vault.join("Projects"), vault.join("Alias")).unwrap();
+        symlink(&vault, vault.join("Projects/loop")).unwrap();
+
+        let collect = |follow: bool| -> Vec<PathBuf> {
+            collect_notes(&vault, &Filter::default().with_symlinks(follow))
+                .iter()
+                .map(|p| p.strip_prefix(&vault).unwrap().to_path_buf())
+                .collect()
+        };
+
+        assert_eq!(collect(false), ["Projects/a.md"].map(PathBuf::from));
+        assert_eq!(
+            collect(true),
+            ["Projects/a.md", "Shared/s.md"].map(PathBuf::from)
+        );
+    }
 }
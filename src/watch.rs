@@ -0,0 +1,92 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+pub struct VaultWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl VaultWatcher {
+    pub fn new(vault_path: &Path) -> notify::Result<Self> {
+        let (tx, events) = channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(vault_path, RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    pub fn next_batch(&self) -> notify::Result<BTreeSet<PathBuf>> {
+        let mut paths = BTreeSet::new();
+        let first = self
+            .events
+            .recv()
+            .map_err(|e| notify::Error::generic(&e.to_string()))?;
+        collect_paths(first?, &mut paths);
+
+        loop {
+            match self.events.recv_timeout(DEBOUNCE) {
+                Ok(event) => collect_paths(event?, &mut paths),
+                Err(RecvTimeoutError::Timeout) => return Ok(paths),
+                Err(e) => return Err(notify::Error::generic(&e.to_string())),
+            }
+        }
+    }
+}
+
+fn collect_paths(event: Event, paths: &mut BTreeSet<PathBuf>) {
+    if !matches!(event.kind, EventKind::Access(_)) {
+        paths.extend(event.paths);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    Added(&'a Path),
+    Removed(&'a Path),
+}
+
+pub fn diff<'a>(old: &'a BTreeSet<PathBuf>, new: &'a BTreeSet<PathBuf>) -> Vec<Change<'a>> {
+    let removed = old.difference(new).map(|p| Change::Removed(p));
+    let added = new.difference(old).map(|p| Change::Added(p));
+    removed.chain(added).collect()
+}
+
+pub fn run_hook(command: &str, vault_path: &Path, note: &Path) -> io::Result<ExitStatus> {
+    Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .arg("ovq")
+        .arg(note)
+        .env("OVQ_NOTE", note)
+        .current_dir(vault_path)
+        .status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old: BTreeSet<PathBuf> = ["a.md", "b.md"].map(PathBuf::from).into();
+        let new: BTreeSet<PathBuf> = ["b.md", "c.md"].map(PathBuf::from).into();
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change::Removed(Path::new("a.md")),
+                Change::Added(Path::new("c.md")),
+            ]
+        );
+        assert!(diff(&new, &new).is_empty());
+    }
+}
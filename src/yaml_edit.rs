@@ -0,0 +1,485 @@
+use serde_yaml::Value;
+use std::ops::Range;
+
+const DEFAULT_INDENT: &str = "  ";
+
+// A top-level `key: value` entry of a block mapping, located by byte offsets into the YAML text.
+struct Entry {
+    key: String,
+    start: usize,
+    value_start: usize,
+    inline: Range<usize>,
+    comment: Option<Range<usize>>,
+    comment_lines: Vec<Range<usize>>,
+    value_end: usize,
+    end: usize,
+    block_indent: Option<String>,
+    block_seq: bool,
+}
+
+// How the value being replaced was written, so the new value can keep its style.
+struct Old<'a> {
+    inline: &'a str,
+    items: Vec<(Value, String)>,
+}
+
+impl Old<'_> {
+    fn item_text(&self, item: &Value) -> Option<String> {
+        self.items
+            .iter()
+            .find(|(value, _)| value == item)
+            .map(|(_, text)| text.clone())
+    }
+}
+
+enum Rendered {
+    Inline(String),
+    Block(String),
+}
+
+pub fn set(yaml: &str, key: &str, value: &Value, newline: &str) -> Option<String> {
+    let entries = entries(yaml)?;
+    let default_indent = entries
+        .iter()
+        .find(|e| e.block_seq)
+        .and_then(|e| e.block_indent.as_deref())
+        .unwrap_or(DEFAULT_INDENT);
+
+    let Some(entry) = entries.iter().find(|e| e.key.eq_ignore_ascii_case(key)) else {
+        let mut out = yaml.to_string();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push_str(newline);
+        }
+        let old = Old {
+            inline: "",
+            items: Vec::new(),
+        };
+        let rendered = render(value, &old, default_indent, newline);
+        out.push_str(&render_key(key)?);
+        out.push(':');
+        out.push_str(&replacement(rendered, "", ""));
+        out.push_str(newline);
+        return Some(out);
+    };
+
+    let old = Old {
+        inline: &yaml[entry.inline.clone()],
+        items: old_items(yaml, entry),
+    };
+    let comment = entry.comment.clone().map_or("", |r| &yaml[r]);
+    let comment_lines: String = entry
+        .comment_lines
+        .iter()
+        .map(|r| format!("{}{}", newline, &yaml[r.clone()]))
+        .collect();
+    let indent = match (entry.block_seq, &entry.block_indent) {
+        (true, Some(indent)) => indent,
+        _ => default_indent,
+    };
+    let rendered = render(value, &old, indent, newline);
+    Some(format!(
+        "{}{}{}",
+        &yaml[..entry.value_start],
+        replacement(rendered, comment, &comment_lines),
+        &yaml[entry.value_end..]
+    ))
+}
+
+pub fn unset(yaml: &str, key: &str) -> Option<String> {
+    let entries = entries(yaml)?;
+    Some(
+        match entries.iter().find(|e| e.key.eq_ignore_ascii_case(key)) {
+            Some(entry) => format!("{}{}", &yaml[..entry.start], &yaml[entry.end..]),
+            None => yaml.to_string(),
+        },
+    )
+}
+
+fn old_items(yaml: &str, entry: &Entry) -> Vec<(Value, String)> {
+    let inline = &yaml[entry.inline.clone()];
+    let texts: Vec<&str> = match inline.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        Some(inner) => split_flow(inner),
+        None if entry.block_seq => yaml[entry.inline.end..entry.value_end]
+            .lines()
+            .filter_map(|line| {
+                let item = line.trim_start().strip_prefix('-')?;
+                (item.is_empty() || item.starts_with(char::is_whitespace)).then(|| item.trim())
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    texts
+        .into_iter()
+        .filter_map(|text| Some((serde_yaml::from_str(text).ok()?, text.to_string())))
+        .collect()
+}
+
+fn split_flow(inner: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut start, mut depth, mut skip) = (0, 0, 0);
+    for (i, c) in inner.char_indices() {
+        if i < skip {
+            continue;
+        }
+        match c {
+            '"' | '\'' if inner[start..i].trim().is_empty() => {
+                skip = quote_end(&inner[i + 1..], c).map_or(inner.len(), |end| i + end + 2);
+            }
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(inner[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn replacement(rendered: Rendered, comment: &str, comment_lines: &str) -> String {
+    match rendered {
+        Rendered::Inline(text) if text.is_empty() => format!("{}{}", comment, comment_lines),
+        Rendered::Inline(text) => format!(" {}{}{}", text, comment, comment_lines),
+        Rendered::Block(lines) => format!("{}{}{}", comment, comment_lines, lines),
+    }
+}
+
+fn entries(yaml: &str) -> Option<Vec<Entry>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut comments = Vec::new();
+    let mut offset = 0;
+
+    for line in yaml.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let text = line.trim_end_matches(['\n', '\r']);
+        let text_end = start + text.len();
+        if text.trim().is_empty() {
+            continue;
+        }
+        // Comment lines only belong to a value when more of it follows; an indented one
+        // trailing the value is still removed together with its entry.
+        if text.trim_start().starts_with('#') {
+            if let (Some(entry), true) = (entries.last_mut(), text.starts_with([' ', '\t'])) {
+                entry.end = offset;
+            }
+            comments.push(start..text_end);
+            continue;
+        }
+
+        match text.chars().next()? {
+            ' ' | '\t' | '-' => {
+                let entry = entries.last_mut()?;
+                entry.comment_lines.append(&mut comments);
+                entry.value_end = text_end;
+                entry.end = offset;
+                if entry.block_indent.is_none() {
+                    let body = text.trim_start();
+                    entry.block_indent = Some(text[..text.len() - body.len()].to_string());
+                    entry.block_seq = body == "-" || body.starts_with("- ");
+                }
+            }
+            _ => {
+                comments.clear();
+                let (key, colon) = parse_key(text)?;
+                let value_start = start + colon + 1;
+                let rest = &text[colon + 1..];
+                let (value, comment) = split_comment(rest);
+                entries.push(Entry {
+                    key,
+                    start,
+                    value_start,
+                    inline: value_start + value.start..value_start + value.end,
+                    comment: comment.map(|c| value_start + c..text_end),
+                    comment_lines: Vec::new(),
+                    value_end: text_end,
+                    end: offset,
+                    block_indent: None,
+                    block_seq: false,
+                });
+            }
+        }
+    }
+
+    Some(entries)
+}
+
+fn parse_key(line: &str) -> Option<(String, usize)> {
+    match line.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let close = quote_end(&line[1..], quote)? + 1;
+            let key = serde_yaml::from_str::<String>(&line[..=close]).ok()?;
+            let colon = close + 1 + line[close + 1..].find(|c: char| !c.is_whitespace())?;
+            line[colon..].starts_with(':').then_some((key, colon))
+        }
+        '?' | '&' | '*' | '!' | '{' | '[' | '|' | '>' | '%' | '@' | '`' => None,
+        _ => {
+            let colon = line.char_indices().find_map(|(i, c)| {
+                let after = line[i + 1..].chars().next();
+                (c == ':' && after.is_none_or(char::is_whitespace)).then_some(i)
+            })?;
+            Some((line[..colon].trim_end().to_string(), colon))
+        }
+    }
+}
+
+// Splits the text after `key:` into the value and a trailing ` # comment`, ignoring `#` inside quotes.
+fn split_comment(rest: &str) -> (Range<usize>, Option<usize>) {
+    let value_start = rest.len() - rest.trim_start().len();
+    let mut skip = 0;
+    let mut previous: Option<char> = None;
+
+    for (i, c) in rest.char_indices() {
+        if i < skip {
+            continue;
+        }
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            let value_end = rest[..i].trim_end().len();
+            return (value_start.min(value_end)..value_end, Some(value_end));
+        }
+        if matches!(c, '"' | '\'')
+            && rest[..i]
+                .trim_end()
+                .chars()
+                .last()
+                .is_none_or(|p| matches!(p, '[' | '{' | ','))
+        {
+            skip = quote_end(&rest[i + 1..], c).map_or(rest.len(), |end| i + end + 2);
+        }
+        previous = Some(c);
+    }
+
+    (value_start..rest.trim_end().len().max(value_start), None)
+}
+
+// Finds the closing quote of a scalar that starts right after `quote`, skipping `\"` and `''` escapes.
+fn quote_end(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && quote == '"' {
+            chars.next();
+        } else if c == quote {
+            if quote == '\'' && text[i + 1..].starts_with('\'') {
+                chars.next();
+            } else {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+fn render(value: &Value, old: &Old, indent: &str, newline: &str) -> Rendered {
+    match value {
+        Value::Sequence(items) if items.is_empty() => Rendered::Inline("[]".to_string()),
+        Value::Sequence(items) => {
+            let flow = old.inline.starts_with('[');
+            let quote = if flow {
+                quote_of_flow(old.inline)
+            } else {
+                old.items.first().and_then(|(_, text)| quote_of(text))
+            };
+            let texts: Option<Vec<String>> = items
+                .iter()
+                .map(|item| {
+                    old.item_text(item)
+                        .or_else(|| render_scalar(item, quote, flow))
+                })
+                .collect();
+            match texts {
+                Some(texts) if flow => Rendered::Inline(format!("[{}]", texts.join(", "))),
+                Some(texts) => Rendered::Block(
+                    texts
+                        .iter()
+                        .map(|text| match text.as_str() {
+                            "" => format!("{}{}-", newline, indent),
+                            text => format!("{}{}- {}", newline, indent, text),
+                        })
+                        .collect(),
+                ),
+                None => Rendered::Block(block(value, indent, newline)),
+            }
+        }
+        Value::Mapping(map) if map.is_empty() => Rendered::Inline("{}".to_string()),
+        Value::Mapping(_) => Rendered::Block(block(value, DEFAULT_INDENT, newline)),
+        _ => {
+            let quote = quote_of(old.inline);
+            match render_scalar(value, quote, false) {
+                Some(text) => Rendered::Inline(text),
+                None => Rendered::Block(block(value, DEFAULT_INDENT, newline)),
+            }
+        }
+    }
+}
+
+fn quote_of_flow(old: &str) -> Option<char> {
+    quote_of(old.trim_start_matches('[').trim_start())
+}
+
+fn quote_of(text: &str) -> Option<char> {
+    text.chars().next().filter(|c| matches!(c, '"' | '\''))
+}
+
+fn render_scalar(value: &Value, quote: Option<char>, flow: bool) -> Option<String> {
+    match value {
+        Value::Null if flow => Some("null".to_string()),
+        Value::Null => Some(String::new()),
+        Value::Bool(_) | Value::Number(_) => Some(plain(value)?),
+        Value::String(s) => Some(match quote {
+            Some('\'') if !s.contains('\n') => format!("'{}'", s.replace('\'', "''")),
+            Some(_) => serde_json::to_string(s).ok()?,
+            None => {
+                let text = plain(value)?;
+                let unsafe_in_flow = flow && text.contains([',', '[', ']', '{', '}']);
+                if text.contains('\n') || text.starts_with(['|', '>']) || unsafe_in_flow {
+                    serde_json::to_string(s).ok()?
+                } else {
+                    text
+                }
+            }
+        }),
+        _ => None,
+    }
+}
+
+fn render_key(key: &str) -> Option<String> {
+    let text = plain(&Value::from(key))?;
+    Some(if text.contains('\n') || text.contains(": ") {
+        serde_json::to_string(key).ok()?
+    } else {
+        text
+    })
+}
+
+fn plain(value: &Value) -> Option<String> {
+    let text = serde_yaml::to_string(value).ok()?;
+    Some(text.trim_end().to_string())
+}
+
+fn block(value: &Value, indent: &str, newline: &str) -> String {
+    serde_yaml::to_string(value)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| format!("{}{}{}", newline, indent, line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_set_preserves_layout() {
+        let yaml = "\
+# Project note
+title: 'Plan: Q3'   # shown in lists
+tags: [work, \"q3\"]
+aliases:
+    - \"Plan\" # original
+status: active
+";
+        let edited = set(yaml, "title", &value("'Plan: Q4'"), "\n").unwrap();
+        let edited = set(&edited, "tags", &value("[work, q3, q4]"), "\n").unwrap();
+        let edited = set(&edited, "aliases", &value("[Plan, Q4]"), "\n").unwrap();
+        let edited = set(&edited, "Status", &value("done"), "\n").unwrap();
+        let edited = set(&edited, "related", &value("[a]"), "\n").unwrap();
+        let edited = set(&edited, "priority", &value("2"), "\n").unwrap();
+
+        assert_eq!(
+            edited,
+            "\
+# Project note
+title: 'Plan: Q4'   # shown in lists
+tags: [work, \"q3\", q4]
+aliases:
+    - \"Plan\" # original
+    - \"Q4\"
+status: done
+related:
+    - a
+priority: 2
+"
+        );
+
+        let yaml = "related:\n  - \"[[A]]\"\n";
+        let edited = set(yaml, "related", &value(r#"["[[A]]", "[[B]]"]"#), "\n").unwrap();
+        assert_eq!(edited, "related:\n  - \"[[A]]\"\n  - \"[[B]]\"\n");
+    }
+
+    #[test]
+    fn test_set_changes_value_kind() {
+        let yaml = "tags:\n- a\n- b\ndue: # set later\nnote: \"x\"\n";
+        let edited = set(yaml, "tags", &value("none"), "\n").unwrap();
+        let edited = set(&edited, "due", &value("2024-05-01"), "\n").unwrap();
+        let edited = set(&edited, "note", &value("say \"hi\""), "\n").unwrap();
+        assert_eq!(
+            edited,
+            "tags: none\ndue: 2024-05-01 # set later\nnote: \"say \\\"hi\\\"\"\n"
+        );
+
+        let edited = set("tags: x\n", "tags", &value("[a, b]"), "\r\n").unwrap();
+        assert_eq!(edited, "tags:\r\n  - a\r\n  - b\n");
+        let edited = set("a: 1", "b", &value("~"), "\n").unwrap();
+        assert_eq!(edited, "a: 1\nb:\n");
+    }
+
+    #[test]
+    fn test_set_keeps_comment_lines() {
+        let yaml = "tags:\n  # work tags\n  - a\n# between\n  - b\n  # trailing\nnext: 1\n";
+        let edited = set(yaml, "tags", &value("[a, b, c]"), "\n").unwrap();
+        assert_eq!(
+            edited,
+            "tags:\n  # work tags\n# between\n  - a\n  - b\n  - c\n  # trailing\nnext: 1\n"
+        );
+
+        let edited = set(
+            "tags:\n  # work tags\n  - a\n",
+            "tags",
+            &value("none"),
+            "\n",
+        )
+        .unwrap();
+        assert_eq!(edited, "tags: none\n  # work tags\n");
+        assert_eq!(unset(yaml, "tags").unwrap(), "next: 1\n");
+    }
+
+    #[test]
+    fn test_set_escaped_quotes() {
+        let edited = set(
+            "title: \"say \\\"hi\\\" # x\"\n",
+            "title",
+            &value("new"),
+            "\n",
+        )
+        .unwrap();
+        assert_eq!(edited, "title: \"new\"\n");
+
+        let yaml = "note: 'it''s # here' # why\ntags: [\"a\\\", b\", c]\n";
+        let edited = set(yaml, "note", &value("x"), "\n").unwrap();
+        let edited = set(&edited, "tags", &value("[\"a\\\", b\", c, d]"), "\n").unwrap();
+        assert_eq!(edited, "note: 'x' # why\ntags: [\"a\\\", b\", c, \"d\"]\n");
+    }
+
+    #[test]
+    fn test_unset_removes_entry_only() {
+        let yaml = "a: 1\n# keep me\ntags:\n  - x\n  - y\n\nb: \"2\" # two\n";
+        assert_eq!(
+            unset(yaml, "TAGS").unwrap(),
+            "a: 1\n# keep me\n\nb: \"2\" # two\n"
+        );
+        assert_eq!(
+            unset(yaml, "b").unwrap(),
+            "a: 1\n# keep me\ntags:\n  - x\n  - y\n\n"
+        );
+        assert_eq!(unset(yaml, "missing").unwrap(), yaml);
+        assert!(unset("- a\n- b\n", "a").is_none());
+        assert!(unset("{a: 1}\n", "a").is_none());
+    }
+}